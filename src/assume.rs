@@ -0,0 +1,156 @@
+use log::{ info, debug };
+use std::collections::BTreeSet;
+
+use crate::searchgraph::SearchGraph;
+use crate::scc::SccFinder;
+use crate::solver::find_assignment;
+use crate::tarjan::Tarjan;
+use crate::literal::Literal;
+
+
+/// View of an implication graph with an extra edge -l -> l for each assumed literal l,
+/// which forces l to be true without changing the underlying graph
+#[derive(Debug,Clone)]
+pub struct AssumptionGraph<'a, G: SearchGraph> {
+    base: &'a G,
+    assumptions: BTreeSet<Literal>,
+}
+
+impl<'a, G: SearchGraph> AssumptionGraph<'a, G> {
+
+    pub fn new(base: &'a G, assumptions: &[Literal]) -> AssumptionGraph<'a, G> {
+        AssumptionGraph {
+            base,
+            assumptions: assumptions.iter().cloned().collect(),
+        }
+    }
+}
+
+impl<'a, G: SearchGraph> SearchGraph for AssumptionGraph<'a, G> {
+
+	fn get_vertex_ids(&self) -> Vec<isize> {
+        self.base.get_vertex_ids()
+    }
+
+	fn get_outgoing_vertex_ids(&self, vertex: isize) -> Vec<isize> {
+        let mut outgoing = self.base.get_outgoing_vertex_ids(vertex);
+        if let Some(assumed) = Literal::new(vertex).ok().map(Literal::negate).filter(|literal| self.assumptions.contains(literal)) {
+            outgoing.push(assumed.value());
+        }
+        outgoing
+    }
+
+	fn get_incoming_vertex_ids(&self, vertex: isize) -> Vec<isize> {
+        let mut incoming = self.base.get_incoming_vertex_ids(vertex);
+        if let Some(assumed) = Literal::new(vertex).ok().filter(|literal| self.assumptions.contains(literal)) {
+            incoming.push(assumed.negate().value());
+        }
+        incoming
+    }
+}
+
+
+/// Result of solving under a set of assumptions
+#[derive(Debug,Clone,PartialEq)]
+pub enum AssumptionResult {
+    /// assignment (entry i is variable i+1) that meets the constraints and the assumptions
+    Satisfiable(Vec<bool>),
+    /// the assumptions that can't hold together (empty if the constraints can't be met
+    /// even without any assumptions)
+    Unsatisfiable(Vec<Literal>),
+}
+
+
+/// Finds a pair of assumptions a, b (possibly the same one) where a implies -b, which
+/// means they can't both be true.  When the constraints themselves can be met there is
+/// always such a pair if the assumptions can't be satisfied.
+fn failed_assumptions<G: SearchGraph>(graph: &G, assumptions: &[Literal]) -> Vec<Literal> {
+
+    for first in assumptions {
+        let reached = graph.reachable_from(first.value());
+        if let Some(second) = assumptions.iter().find(|second| reached.contains(&second.negate().value())) {
+            debug!("Assumption {} implies {}",first,second.negate());
+            if first == second {
+                return vec!(*first);
+            }
+            return vec!(*first,*second);
+        }
+    }
+    Vec::<Literal>::new()
+}
+
+/// Checks whether the constraints can be met when the assumed literals are forced to be
+/// true, without changing the graph
+pub fn solve_with_assumptions<G: SearchGraph>(graph: &G, num_variables: usize, assumptions: &[Literal]) -> Result<AssumptionResult,String> {
+
+    for literal in assumptions {
+        if literal.var().number() > num_variables {
+            return Err(format!("Invalid assumption {} - variables must be between 1 and {}",literal,num_variables));
+        }
+    }
+
+    let assumed = AssumptionGraph::new(graph,assumptions);
+    let mut k = Tarjan::new(&assumed);
+    k.find_scc();
+    if let Some(assignment) = find_assignment(&k,num_variables) {
+        info!("Satisfiable with assumptions {:?}",assumptions);
+        return Ok(AssumptionResult::Satisfiable(assignment));
+    }
+
+    // work out whether it's the constraints or the assumptions that can't be met
+    let mut base_k = Tarjan::new(graph);
+    base_k.find_scc();
+    if find_assignment(&base_k,num_variables).is_none() {
+        info!("Constraints can't be met without any assumptions");
+        return Ok(AssumptionResult::Unsatisfiable(Vec::<Literal>::new()));
+    }
+    let failed = failed_assumptions(graph,assumptions);
+    info!("Assumptions {:?} can't be met together",failed);
+    Ok(AssumptionResult::Unsatisfiable(failed))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::assume::{solve_with_assumptions,AssumptionResult};
+    use crate::bruteforce::lit;
+    use crate::dirgraph::DirectedGraph;
+    use crate::graphbuilder::GraphBuilder;
+    use crate::literal::Literal;
+
+    fn add_clause(g: &mut DirectedGraph, first: isize, second: isize) {
+        let mut g = g;
+        let (first, second) = (lit(first), lit(second));
+        g.add_implication(-first,second);
+        g.add_implication(-second,first);
+    }
+
+    fn literals(values: &[isize]) -> Vec<Literal> {
+        values.iter().map(|value| lit(*value)).collect()
+    }
+
+    #[test]
+    fn assumptions() {
+        // (1 or 2) (-1 or 3) (-2 or -3) (3 or 4)
+        let mut graph = DirectedGraph::new();
+        add_clause(&mut graph,1,2);
+        add_clause(&mut graph,-1,3);
+        add_clause(&mut graph,-2,-3);
+        add_clause(&mut graph,3,4);
+        let edge_count = graph.edge_count();
+
+        match solve_with_assumptions(&graph,4,&literals(&[-3])).unwrap() {
+            AssumptionResult::Satisfiable(assignment) => assert_eq!(&assignment[0..3],&[false,true,false]),
+            result => panic!("Unexpected result {:?}",result),
+        }
+        assert_eq!(solve_with_assumptions(&graph,4,&literals(&[2,1])).unwrap(),AssumptionResult::Unsatisfiable(literals(&[2,1])));
+        assert_eq!(solve_with_assumptions(&graph,4,&literals(&[4,-1,-2])).unwrap(),AssumptionResult::Unsatisfiable(literals(&[-1,-2])));
+        assert!(solve_with_assumptions(&graph,4,&literals(&[5])).is_err());
+        // the graph isn't changed
+        assert_eq!(graph.edge_count(),edge_count);
+
+        add_clause(&mut graph,-3,-3);
+        add_clause(&mut graph,-2,-2);
+        assert_eq!(solve_with_assumptions(&graph,4,&literals(&[4])).unwrap(),AssumptionResult::Unsatisfiable(vec!()));
+    }
+}
@@ -0,0 +1,288 @@
+use log::{ info, debug };
+use std::cmp::Reverse;
+use std::collections::{HashMap,VecDeque};
+
+use crate::enumerate::differing_solution;
+use crate::literal::{Literal,Variable};
+use crate::scc::SccFinder;
+use crate::searchgraph::{SearchGraph,VertexMap};
+use crate::solver::find_assignment;
+
+
+/// A literal that is true in every satisfying assignment, along with the chain of
+/// implications that forces it: either -l -> ... -> l, or p -> ... -> l from a forced literal p
+/// whose own chain is -p -> ... -> p
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct ForcedLiteral {
+    pub literal: Literal,
+    pub chain: Vec<Literal>,
+}
+
+/// The literals that hold in every satisfying assignment, and the variables that can
+/// be either true or false
+#[derive(Debug,Clone,Default,PartialEq,Eq)]
+pub struct Backbone {
+    pub forced: Vec<ForcedLiteral>,
+    pub free: Vec<Variable>,
+}
+
+impl Backbone {
+
+    /// Returns the forced value of the variable, or None if it's free
+    pub fn forced_value(&self, var: Variable) -> Option<bool> {
+        self.forced.iter().find(|forced| forced.literal.var() == var).map(|forced| forced.literal.is_positive())
+    }
+}
+
+
+/// Finds the shortest chain of implications from one literal to another, or None if there
+/// isn't one.  SCCs are ranked in reverse topological order, so the search skips any vertex
+/// with a lower rank than the destination as it can't reach it
+pub fn implication_chain<G: SearchGraph>(graph: &G, k: &dyn SccFinder, from: Literal, to: Literal) -> Option<Vec<Literal>> {
+
+    let min_rank = k.get_scc_rank(to.value())?;
+    let mut parent = HashMap::<isize,isize>::new();
+    let mut queue = VecDeque::from(vec!(from.value()));
+    parent.insert(from.value(),from.value());
+    while let Some(current) = queue.pop_front() {
+        if current == to.value() {
+            let mut chain = vec!(to);
+            let mut vertex = current;
+            while vertex != from.value() {
+                vertex = parent[&vertex];
+                chain.push(Literal::new(vertex).ok()?);
+            }
+            chain.reverse();
+            return Some(chain);
+        }
+        for dest in graph.get_outgoing_vertex_ids(current) {
+            if k.get_scc_rank(dest).is_some_and(|rank| rank >= min_rank) && !parent.contains_key(&dest) {
+                parent.insert(dest,current);
+                queue.push_back(dest);
+            }
+        }
+    }
+    None
+}
+
+/// Follows the parent map back from one literal to the start of the search, returning the chain
+/// from the start to the literal
+fn parent_chain<G: SearchGraph>(parent: &VertexMap<G,isize>, from: Literal, to: Literal) -> Option<Vec<Literal>> {
+
+    let mut chain = vec!(to);
+    let mut vertex = to.value();
+    while vertex != from.value() {
+        vertex = *parent.get(vertex)?;
+        chain.push(Literal::new(vertex).ok()?);
+    }
+    chain.reverse();
+    Some(chain)
+}
+
+/// Finds the shortest chain from one literal to another like `implication_chain`, using a
+/// parent map shared between searches.  Every vertex the search reaches is added to `visited`,
+/// and the caller removes them from the map before the next search
+fn shared_chain<G: SearchGraph>(graph: &G, k: &dyn SccFinder, parent: &mut VertexMap<G,isize>, visited: &mut Vec<isize>,
+                                from: Literal, to: Literal) -> Option<Vec<Literal>> {
+
+    let min_rank = k.get_scc_rank(to.value())?;
+    parent.insert(from.value(),from.value());
+    visited.push(from.value());
+    let mut next = 0;
+    while next < visited.len() {
+        let current = visited[next];
+        next += 1;
+        if current == to.value() {
+            return parent_chain(parent,from,to);
+        }
+        for dest in graph.get_outgoing_vertex_ids(current) {
+            if k.get_scc_rank(dest).is_some_and(|rank| rank >= min_rank) && !parent.contains_key(dest) {
+                parent.insert(dest,current);
+                visited.push(dest);
+            }
+        }
+    }
+    None
+}
+
+/// Finds the backbone of the formula from its implication graph and SCCs, or None if the
+/// formula can't be satisfied.
+///
+/// A literal l is forced exactly when -l reaches l, and only the literal the SCC assignment
+/// makes true can be.  A second solution that differs from that assignment wherever it can
+/// shows most of the free variables without any search.  Everything a forced literal implies
+/// is forced too, so once one is found a single search from it marks the literals it implies,
+/// each with the shortest chain from the forced literal.  When -l doesn't reach l, nothing -l
+/// reaches can reach its own negation either, so the variables of the false literals the search
+/// passed through are free.  The literals are tried
+/// in topological order so that the literals that imply the most are tried first
+pub fn find_backbone<G: SearchGraph>(graph: &G, k: &dyn SccFinder, num_variables: usize) -> Option<Backbone> {
+
+    let assignment = find_assignment(k,num_variables)?;
+    let true_literal = |index: usize| Variable::from_index(index).literal(assignment[index]);
+    let mut order : Vec<usize> = (0..num_variables).collect();
+    order.sort_by_key(|index| Reverse(k.get_scc_rank(true_literal(*index).value())));
+    let other = differing_solution(graph,&assignment,order.iter().map(|index| Variable::from_index(*index)).collect())?;
+
+    let mut chains : Vec<Option<Vec<Literal>>> = vec![None; num_variables];
+    let mut free : Vec<bool> = assignment.iter().zip(other.iter()).map(|(value, other)| value != other).collect();
+    let mut parent = VertexMap::<G,isize>::new(graph);
+    let mut visited = Vec::<isize>::new();
+    for index in order {
+        if chains[index].is_some() || free[index] {
+            continue;
+        }
+        let literal = true_literal(index);
+        match shared_chain(graph,k,&mut parent,&mut visited,literal.negate(),literal) {
+            Some(chain) => {
+                debug!("Literal {} is forced by {:?}",literal,chain);
+                chains[index] = Some(chain);
+                for vertex in visited.drain(..) {
+                    parent.remove(vertex);
+                }
+                // mark what the literal implies, searching from it with the same parent map
+                parent.insert(literal.value(),literal.value());
+                visited.push(literal.value());
+                let mut next = 0;
+                while next < visited.len() {
+                    let current = visited[next];
+                    next += 1;
+                    for dest in graph.get_outgoing_vertex_ids(current).into_iter().filter_map(|dest| Literal::new(dest).ok()) {
+                        let dest_index = dest.var().index();
+                        if dest_index < num_variables && chains[dest_index].is_none() {
+                            parent.insert(dest.value(),current);
+                            visited.push(dest.value());
+                            chains[dest_index] = parent_chain(&parent,literal,dest);
+                        }
+                    }
+                }
+            },
+            None => {
+                free[index] = true;
+                for reached in visited.iter().filter_map(|vertex| Literal::new(*vertex).ok()) {
+                    let reached_index = reached.var().index();
+                    if reached_index < num_variables && assignment[reached_index] != reached.is_positive() {
+                        free[reached_index] = true;
+                    }
+                }
+            },
+        }
+        for vertex in visited.drain(..) {
+            parent.remove(vertex);
+        }
+    }
+
+    let mut backbone = Backbone::default();
+    for (index, chain) in chains.into_iter().enumerate() {
+        match chain {
+            Some(chain) => backbone.forced.push(ForcedLiteral { literal: true_literal(index), chain }),
+            None => backbone.free.push(Variable::from_index(index)),
+        }
+    }
+    info!("Backbone has {} forced literals and {} free variables",backbone.forced.len(),backbone.free.len());
+    Some(backbone)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::backbone::{find_backbone,Backbone};
+    use crate::bruteforce::{brute_force_solutions,lit,seeded_formula};
+    use crate::csrgraph::CsrGraph;
+    use crate::generator::GeneratorMode;
+    use crate::literal::{Literal,Variable};
+    use crate::parse::{parse_formula,InputFormat,ParseMode};
+    use crate::scc::SccFinder;
+    use crate::searchgraph::SearchGraph;
+    use crate::tarjan::Tarjan;
+
+    /// Checks each chain is a path through the graph ending at its literal, which starts either
+    /// from the literal's negation or from a forced literal whose own chain does
+    fn check_chains<G: SearchGraph>(graph: &G, backbone: &Backbone) {
+        for forced in &backbone.forced {
+            assert_eq!(forced.chain.last(),Some(&forced.literal));
+            for pair in forced.chain.windows(2) {
+                assert!(graph.get_outgoing_vertex_ids(pair[0].value()).contains(&pair[1].value()),
+                        "{} -> {} in the chain for {} isn't an edge",pair[0],pair[1],forced.literal);
+            }
+            let start = forced.chain[0];
+            if start != forced.literal.negate() {
+                let root = backbone.forced.iter().find(|root| root.literal == start).expect("chain doesn't start from a forced literal");
+                assert_eq!(root.chain[0],start.negate());
+            }
+        }
+    }
+
+    #[test]
+    fn forced_literals() {
+        // 1 is forced by -1 -> 2 -> 1, which then forces -3 and through it 5;  2 and 4 are free
+        let formula = parse_formula("5\n1 2\n-2 1\n-1 -3\n3 5\n4 -4\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let mut graph = CsrGraph::new();
+        formula.build_graph(&mut graph);
+        let mut k = Tarjan::new(&graph);
+        k.find_scc();
+        let backbone = find_backbone(&graph,&k,5).unwrap();
+        check_chains(&graph,&backbone);
+        let forced : Vec<Literal> = backbone.forced.iter().map(|forced| forced.literal).collect();
+        assert_eq!(forced,vec!(lit(1),lit(-3),lit(5)));
+        assert_eq!(backbone.forced[0].chain,vec!(lit(-1),lit(2),lit(1)));
+        // -3 and 5 are forced as 1 implies them
+        assert_eq!(backbone.forced[1].chain,vec!(lit(1),lit(-3)));
+        assert_eq!(backbone.forced[2].chain,vec!(lit(1),lit(-3),lit(5)));
+        assert_eq!(backbone.free,vec!(Variable::new(2).unwrap(),Variable::new(4).unwrap()));
+        assert_eq!(backbone.forced_value(Variable::new(3).unwrap()),Some(false));
+
+        let formula = parse_formula("1\n1 1\n-1 -1\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let mut graph = CsrGraph::new();
+        formula.build_graph(&mut graph);
+        let mut k = Tarjan::new(&graph);
+        k.find_scc();
+        assert_eq!(find_backbone(&graph,&k,1),None);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        for seed in 0..200 {
+            let num_variables = 1 + (seed % 8) as usize;
+            let formula = seeded_formula(seed,GeneratorMode::Planted,num_variables,num_variables*2);
+            let mut graph = CsrGraph::new();
+            formula.build_graph(&mut graph);
+            let mut k = Tarjan::new(&graph);
+            k.find_scc();
+            let backbone = find_backbone(&graph,&k,num_variables).unwrap();
+            check_chains(&graph,&backbone);
+            let solutions = brute_force_solutions(num_variables,&formula.clauses);
+            for index in 0..num_variables {
+                let var = Variable::from_index(index);
+                let values : Vec<bool> = solutions.iter().map(|assignment| assignment[index]).collect();
+                let expected = if values.iter().all(|v| *v) { Some(true) } else if values.iter().all(|v| !*v) { Some(false) } else { None };
+                assert_eq!(backbone.forced_value(var),expected,"seed {} variable {}",seed,var);
+            }
+        }
+    }
+
+    #[test]
+    fn long_implication_chain() {
+        // 1 is forced and implies 2 .. n, while n+1 -> n+2 -> .. -> n+m are all free.  Each
+        // implied literal's chain starts from 1, so the forced part is kept shorter
+        const FORCED_LENGTH : usize = 2_000;
+        const FREE_LENGTH : usize = 100_000;
+        let num_variables = FORCED_LENGTH + FREE_LENGTH;
+        let mut text = format!("{}\n1 1\n",num_variables);
+        for var in (1..FORCED_LENGTH).chain(FORCED_LENGTH+1..num_variables) {
+            text.push_str(&format!("-{} {}\n",var,var+1));
+        }
+        let formula = parse_formula(text.as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let mut graph = CsrGraph::new();
+        formula.build_graph(&mut graph);
+        let mut k = Tarjan::new(&graph);
+        k.find_scc();
+        let backbone = find_backbone(&graph,&k,num_variables).unwrap();
+        check_chains(&graph,&backbone);
+        assert_eq!(backbone.forced.len(),FORCED_LENGTH);
+        assert_eq!(backbone.free.len(),FREE_LENGTH);
+        assert_eq!(backbone.forced[0].chain,vec!(lit(-1),lit(1)));
+        let longest : Vec<Literal> = (1..=FORCED_LENGTH as isize).map(lit).collect();
+        assert_eq!(backbone.forced[FORCED_LENGTH-1].chain,longest);
+    }
+}
@@ -0,0 +1,195 @@
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::thread;
+use std::time::Duration;
+use log::{ info, debug };
+
+use crate::log_files::{get_log_dir,set_thread_log_dir};
+
+
+/// The outcome of solving one of the files in a batch
+#[derive(Debug,Clone)]
+pub struct BatchResult {
+    pub name: String,
+    /// whether the constraints can be met, or why the file couldn't be solved
+    pub outcome: Result<bool,String>,
+    pub num_variables: usize,
+    pub num_clauses: usize,
+    pub elapsed: Duration,
+}
+
+impl BatchResult {
+
+    pub fn failed(name: &str, why: String, elapsed: Duration) -> BatchResult {
+        BatchResult { name: name.to_string(), outcome: Err(why), num_variables: 0, num_clauses: 0, elapsed }
+    }
+}
+
+
+fn is_pattern(name: &str) -> bool {
+    name.contains(['*','?','['])
+}
+
+/// Expands the input names into the list of files to solve.  A directory is replaced by
+/// the files in it and a glob pattern by the files it matches (each sorted by name).
+/// Also returns whether batch mode is needed, which is when there is more than one name
+/// or any name was a directory or pattern
+pub fn expand_inputs(names: &[String]) -> Result<(Vec<String>,bool),String> {
+
+    let mut files = Vec::<String>::new();
+    let mut batch = names.len() > 1;
+    for name in names {
+        let path = Path::new(name);
+        if name != "-" && path.is_dir() {
+            batch = true;
+            let entries = fs::read_dir(path).map_err(|why| format!("couldn't read directory {}: {}",name,why))?;
+            let mut dir_files : Vec<String> = entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_file() && !entry.file_name().to_string_lossy().starts_with('.'))
+                .map(|entry| entry.path().display().to_string())
+                .collect();
+            dir_files.sort();
+            files.extend(dir_files);
+        }
+        else if is_pattern(name) && !path.exists() {
+            batch = true;
+            let paths = glob::glob(name).map_err(|why| format!("invalid pattern {}: {}",name,why))?;
+            let mut matched : Vec<String> = paths
+                .filter_map(|path| path.ok())
+                .filter(|path| path.is_file())
+                .map(|path| path.display().to_string())
+                .collect();
+            if matched.is_empty() {
+                return Err(format!("no files match {}",name));
+            }
+            matched.sort();
+            files.extend(matched);
+        }
+        else {
+            files.push(name.clone());
+        }
+    }
+    debug!("Expanded inputs {:?} to {} files",names,files.len());
+    Ok((files,batch))
+}
+
+
+/// Name of the log subdirectory for a file in the batch, e.g. log/003_input_1.txt
+fn file_log_dir(base: &str, index: usize, name: &str) -> String {
+    let file_name = Path::new(name).file_name().map_or("stdin".to_string(), |f| f.to_string_lossy().to_string());
+    format!("{}/{:03}_{}",base,index+1,file_name)
+}
+
+/// Solves each of the files using the given number of worker threads, with each file
+/// logging into its own subdirectory of the log directory.  The results are in the
+/// same order as the files
+pub fn run_batch<S>(files: &[String], jobs: usize, stack_size: Option<usize>, solve: S) -> Vec<BatchResult>
+where S: Fn(&str) -> BatchResult + Sync,
+{
+    let base_log_dir = get_log_dir();
+    let next_file = AtomicUsize::new(0);
+    let jobs = jobs.clamp(1,files.len().max(1));
+    info!("Solving {} files with {} threads",files.len(),jobs);
+
+    let worker = || {
+        let mut results = Vec::<(usize,BatchResult)>::new();
+        loop {
+            let index = next_file.fetch_add(1,Ordering::SeqCst);
+            if index >= files.len() {
+                break;
+            }
+            set_thread_log_dir(Some(&file_log_dir(&base_log_dir,index,&files[index])));
+            let result = solve(&files[index]);
+            info!("{} solved in {:?}",files[index],result.elapsed);
+            results.push((index,result));
+        }
+        set_thread_log_dir(None);
+        results
+    };
+
+    let mut results = thread::scope(|scope| {
+        let handles : Vec<_> = (0..jobs).map(|job| {
+            let mut builder = thread::Builder::new().name(format!("Batch Worker {}",job));
+            if let Some(size) = stack_size {
+                builder = builder.stack_size(size);
+            }
+            builder.spawn_scoped(scope,worker).unwrap()
+        }).collect();
+        handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect::<Vec<_>>()
+    });
+    results.sort_by_key(|(index,_)| *index);
+    results.into_iter().map(|(_,result)| result).collect()
+}
+
+
+/// The results concatenated into one string, e.g. 1011 (with ? for files that couldn't be solved)
+pub fn result_bits(results: &[BatchResult]) -> String {
+    results.iter().map(|result| match result.outcome {
+        Ok(true) => '1',
+        Ok(false) => '0',
+        Err(_) => '?',
+    }).collect()
+}
+
+/// Prints a line for each file with its result, size and time, followed by the concatenated results
+pub fn print_batch(results: &[BatchResult]) {
+
+    let width = results.iter().map(|result| result.name.len()).max().unwrap_or(0).max(4);
+    println!("{:<width$}  {:>6}  {:>9}  {:>9}  {:>10}","file","result","variables","clauses","time (ms)",width=width);
+    for result in results {
+        let outcome = match &result.outcome {
+            Ok(true) => "1",
+            Ok(false) => "0",
+            Err(_) => "error",
+        };
+        println!("{:<width$}  {:>6}  {:>9}  {:>9}  {:>10}",result.name,outcome,result.num_variables,result.num_clauses,
+                 result.elapsed.as_millis(),width=width);
+    }
+    for result in results {
+        if let Err(why) = &result.outcome {
+            eprintln!("{}: {}",result.name,why);
+        }
+    }
+    println!("{}",result_bits(results));
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+    use crate::batch::{expand_inputs,run_batch,result_bits,BatchResult};
+
+    #[test]
+    fn expand_and_run() {
+        let dir = std::env::temp_dir().join("sat2_batch_test");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["input_2.txt","input_1.txt","notes.md"] {
+            fs::write(dir.join(name),"1\n1 1\n").unwrap();
+        }
+
+        let dir_name = dir.display().to_string();
+        let pattern = format!("{}/input_*.txt",dir_name);
+        let no_match = format!("{}/*.cnf",dir_name);
+        let (files, batch) = expand_inputs(&[dir_name]).unwrap();
+        assert!(batch);
+        assert_eq!(files.len(),3);
+        assert!(files[0].ends_with("input_1.txt"));
+        let (files, batch) = expand_inputs(&[pattern]).unwrap();
+        assert!(batch);
+        assert_eq!(files.len(),2);
+        let (single, batch) = expand_inputs(&[files[1].clone()]).unwrap();
+        assert!(!batch);
+        assert_eq!(single,vec!(files[1].clone()));
+        assert!(expand_inputs(&[no_match]).is_err());
+
+        let results = run_batch(&files,4,None,|name| {
+            let outcome = if name.ends_with("1.txt") { Ok(true) } else { Err("bad".to_string()) };
+            BatchResult { name: name.to_string(), outcome, num_variables: 1, num_clauses: 1, elapsed: Duration::ZERO }
+        });
+        assert_eq!(result_bits(&results),"1?");
+        assert_eq!(results[1].name,files[1]);
+    }
+}
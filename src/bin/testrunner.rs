@@ -0,0 +1,251 @@
+extern crate clap;
+
+use clap::Parser;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path,PathBuf};
+use std::process::{self,Command};
+use std::time::{Duration,Instant};
+
+
+/// Runs the test cases described by a run_tc.ini style config.  Each input_* file is run
+/// through the configured command and its output compared with the matching output_* file
+#[derive(Parser, Debug)]
+#[clap(name = "testrunner")]
+#[clap(author = "Marvin Mednick")]
+#[clap(version = "1.0")]
+#[clap(about = "Test case runner", long_about = "Runs the test cases using the command from a test case config (e.g. run_tc.ini)")]
+struct RunnerArgs {
+
+    #[clap(value_parser, default_value="run_tc.ini")]
+    /// config file with the command to run (command=... ##FILE##) and the expected output prefix (out_prefix=...)
+    config: String,
+
+    #[clap(short, long, default_value=".")]
+    /// directory with the test case files
+    dir: String,
+
+    #[clap(long, default_value="input")]
+    /// prefix of the test case input files
+    in_prefix: String,
+
+    #[clap(short, long)]
+    /// only runs the test cases with names that contain the given text
+    filter: Option<String>,
+
+    #[clap(short, long, takes_value=false)]
+    /// shows the differences for the test cases that fail
+    verbose: bool,
+}
+
+
+const FILE_MARKER: &str = "##FILE##";
+
+/// The settings from the [config] section of the ini file
+#[derive(Debug,Clone,PartialEq)]
+struct TestConfig {
+    /// command to run, with ##FILE## replaced by the input file
+    command: String,
+    /// prefix that replaces the input prefix to get the expected output file name
+    out_prefix: String,
+}
+
+#[derive(Debug)]
+enum Outcome {
+    Pass,
+    Fail(Vec<String>),
+    Error(String),
+}
+
+#[derive(Debug)]
+struct TestResult {
+    name: String,
+    outcome: Outcome,
+    elapsed: Duration,
+}
+
+
+/// Reads the sections of an ini file into a map of section name to its key/value pairs
+fn parse_ini(text: &str) -> Result<HashMap<String,HashMap<String,String>>,String> {
+
+    let mut sections = HashMap::<String,HashMap<String,String>>::new();
+    let mut section = String::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            section = line[1..line.len()-1].trim().to_string();
+            sections.entry(section.clone()).or_default();
+            continue;
+        }
+        match line.split_once('=') {
+            Some((key, value)) => {
+                sections.entry(section.clone()).or_default().insert(key.trim().to_string(),value.trim().to_string());
+            },
+            None => return Err(format!("line {}: expected key=value but found '{}'",index+1,line)),
+        }
+    }
+    Ok(sections)
+}
+
+fn read_config(file_name: &str) -> Result<TestConfig,String> {
+
+    let text = fs::read_to_string(file_name).map_err(|why| format!("couldn't read {}: {}",file_name,why))?;
+    let sections = parse_ini(&text).map_err(|why| format!("{}: {}",file_name,why))?;
+    let config = sections.get("config").ok_or(format!("{}: no [config] section",file_name))?;
+    let command = config.get("command").ok_or(format!("{}: no command in [config]",file_name))?;
+    if !command.contains(FILE_MARKER) {
+        return Err(format!("{}: command '{}' doesn't contain {}",file_name,command,FILE_MARKER));
+    }
+    Ok(TestConfig {
+        command: command.clone(),
+        out_prefix: config.get("out_prefix").cloned().unwrap_or_else(|| "output".to_string()),
+    })
+}
+
+/// Finds the input files in the directory, sorted by name
+fn find_inputs(dir: &str, in_prefix: &str, filter: &Option<String>) -> Result<Vec<PathBuf>,String> {
+
+    let entries = fs::read_dir(dir).map_err(|why| format!("couldn't read directory {}: {}",dir,why))?;
+    let mut inputs : Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy();
+            name.starts_with(in_prefix) && filter.as_ref().map_or(true,|filter| name.contains(filter.as_str()))
+        }))
+        .collect();
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// The expected output file for an input, e.g. input_10_2.txt -> output_10_2.txt
+fn expected_file(input: &Path, in_prefix: &str, out_prefix: &str) -> PathBuf {
+    let name = input.file_name().unwrap().to_string_lossy();
+    input.with_file_name(format!("{}{}",out_prefix,&name[in_prefix.len()..]))
+}
+
+/// The non blank lines with the trailing whitespace removed
+fn normalize(text: &str) -> Vec<String> {
+    text.lines().map(|line| line.trim_end().to_string()).filter(|line| !line.is_empty()).collect()
+}
+
+/// Lists the lines that differ between the expected and actual output
+fn differences(expected: &[String], actual: &[String]) -> Vec<String> {
+    let mut diffs = Vec::<String>::new();
+    for index in 0..expected.len().max(actual.len()) {
+        let expected_line = expected.get(index).map_or("<missing>", |line| line.as_str());
+        let actual_line = actual.get(index).map_or("<missing>", |line| line.as_str());
+        if expected_line != actual_line {
+            diffs.push(format!("line {}: expected '{}' got '{}'",index+1,expected_line,actual_line));
+        }
+    }
+    diffs
+}
+
+fn run_test(config: &TestConfig, input: &Path, expected: &Path) -> Outcome {
+
+    let expected_text = match fs::read_to_string(expected) {
+        Ok(text) => text,
+        Err(why) => return Outcome::Error(format!("couldn't read {}: {}",expected.display(),why)),
+    };
+    let input_name = input.display().to_string();
+    let command_line = config.command.replace(FILE_MARKER,&input_name);
+    let mut words = command_line.split_whitespace();
+    let program = match words.next() {
+        Some(program) => program,
+        None => return Outcome::Error("empty command".to_string()),
+    };
+    let output = match Command::new(program).args(words).output() {
+        Ok(output) => output,
+        Err(why) => return Outcome::Error(format!("couldn't run {}: {}",program,why)),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Outcome::Error(format!("{} exited with {} {}",program,output.status,stderr.trim()));
+    }
+
+    let diffs = differences(&normalize(&expected_text),&normalize(&String::from_utf8_lossy(&output.stdout)));
+    if diffs.is_empty() {
+        Outcome::Pass
+    }
+    else {
+        Outcome::Fail(diffs)
+    }
+}
+
+fn main() {
+
+    let args = RunnerArgs::parse();
+
+    let config = match read_config(&args.config) {
+        Ok(config) => config,
+        Err(why) => {
+            eprintln!("{}",why);
+            process::exit(2);
+        },
+    };
+    let inputs = match find_inputs(&args.dir,&args.in_prefix,&args.filter) {
+        Ok(inputs) => inputs,
+        Err(why) => {
+            eprintln!("{}",why);
+            process::exit(2);
+        },
+    };
+    println!("Running {} test cases with '{}'",inputs.len(),config.command);
+
+    let mut results = Vec::<TestResult>::new();
+    let start = Instant::now();
+    for input in &inputs {
+        let expected = expected_file(input,&args.in_prefix,&config.out_prefix);
+        let test_start = Instant::now();
+        let outcome = run_test(&config,input,&expected);
+        let result = TestResult { name: input.file_name().unwrap().to_string_lossy().to_string(), outcome, elapsed: test_start.elapsed() };
+        match &result.outcome {
+            Outcome::Pass => println!("PASS   {} ({} ms)",result.name,result.elapsed.as_millis()),
+            Outcome::Fail(diffs) => {
+                println!("FAIL   {} ({} ms)",result.name,result.elapsed.as_millis());
+                if args.verbose {
+                    for diff in diffs {
+                        println!("         {}",diff);
+                    }
+                }
+            },
+            Outcome::Error(why) => println!("ERROR  {} - {}",result.name,why),
+        }
+        results.push(result);
+    }
+
+    let passed = results.iter().filter(|result| matches!(result.outcome, Outcome::Pass)).count();
+    let failed = results.iter().filter(|result| matches!(result.outcome, Outcome::Fail(_))).count();
+    let errors = results.len() - passed - failed;
+    println!("{} passed, {} failed, {} errors in {:.3} s",passed,failed,errors,start.elapsed().as_secs_f64());
+    if passed != results.len() {
+        process::exit(1);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use crate::{parse_ini,expected_file,normalize,differences};
+
+    #[test]
+    fn config_and_expected_files() {
+        let sections = parse_ini("; comment\n[config]\ncommand=sat2 ##FILE## \nout_prefix = output\n").unwrap();
+        assert_eq!(sections["config"]["command"],"sat2 ##FILE##");
+        assert_eq!(sections["config"]["out_prefix"],"output");
+        assert!(parse_ini("[config]\ncommand\n").is_err());
+
+        let expected = expected_file(Path::new("tests/input_random_1_2.txt"),"input","output");
+        assert_eq!(expected,Path::new("tests/output_random_1_2.txt"));
+
+        let expected = normalize("1\n\n");
+        assert!(differences(&expected,&normalize("1  \n")).is_empty());
+        assert_eq!(differences(&expected,&normalize("0\n")).len(),1);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::generator::{Generator,GeneratorMode};
+use crate::literal::Literal;
+use crate::parse::{Clause,Formula};
+
+
+/// Checks that every clause has at least one true literal.  Entry i of the assignment
+/// is the value of variable i+1
+pub fn satisfies(assignment: &[bool], clauses: &[Clause]) -> bool {
+    let is_true = |literal: Literal| literal.is_true(assignment) == Some(true);
+    clauses.iter().all(|clause| is_true(clause.first) || is_true(clause.second))
+}
+
+/// Every assignment of the variables, in order
+fn all_assignments(num_variables: usize) -> impl Iterator<Item=Vec<bool>> {
+    assert!(num_variables <= 20, "brute force is limited to 20 variables");
+    (0..1u32 << num_variables).map(move |bits| (0..num_variables).map(|var| bits & (1 << var) != 0).collect())
+}
+
+/// Reference solver that tries every assignment, so it's only usable for small numbers of variables
+pub fn brute_force(num_variables: usize, clauses: &[Clause]) -> Option<Vec<bool>> {
+    all_assignments(num_variables).find(|assignment| satisfies(assignment,clauses))
+}
+
+/// Every assignment that satisfies the clauses, found by trying all of them
+pub fn brute_force_solutions(num_variables: usize, clauses: &[Clause]) -> Vec<Vec<bool>> {
+    all_assignments(num_variables).filter(|assignment| satisfies(assignment,clauses)).collect()
+}
+
+/// Shorthand for a literal that's known to be valid
+pub fn lit(value: isize) -> Literal {
+    Literal::new(value).unwrap()
+}
+
+/// A generated formula for the seeded tests that compare a solver against the brute force ones
+pub fn seeded_formula(seed: u64, mode: GeneratorMode, num_variables: usize, num_clauses: usize) -> Formula {
+    let mut formula = Formula::new(num_variables);
+    formula.clauses = Generator::new(num_variables,seed).generate(mode,num_clauses,1).unwrap();
+    formula
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng,SeedableRng};
+    use crate::bruteforce::{brute_force,brute_force_solutions,lit,satisfies};
+    use crate::csrgraph::CsrGraph;
+    use crate::dirgraph::DirectedGraph;
+    use crate::kosaraju::Kosaraju;
+    use crate::tarjan::Tarjan;
+    use crate::gabow::Gabow;
+    use crate::parse::{read_constraints,Clause,ParseMode};
+    use crate::scc::SccFinder;
+    use crate::searchgraph::SearchGraph;
+    use crate::solver::find_assignment;
+
+    const CASES: u64 = 500;
+
+    /// A random formula in the course format, with up to 3 clauses per variable so that both
+    /// satisfiable and unsatisfiable formulas come up often.  Unit clauses (l l) are included
+    fn random_formula(rng: &mut StdRng) -> String {
+        let num_variables = rng.gen_range(1..=8);
+        let num_clauses = rng.gen_range(0..=3*num_variables);
+        let mut random_literal = || {
+            let var = rng.gen_range(1..=num_variables) as isize;
+            if rng.gen_bool(0.5) { var } else { 0-var }
+        };
+        let mut text = format!("{}\n",num_variables);
+        for _ in 0..num_clauses {
+            text.push_str(&format!("{} {}\n",random_literal(),random_literal()));
+        }
+        text
+    }
+
+    fn check_solution(k: &dyn SccFinder, num_variables: usize, clauses: &[Clause], expected: bool, context: &str) {
+        match find_assignment(k,num_variables) {
+            Some(assignment) => {
+                assert!(expected,"{} found an assignment for an unsatisfiable formula",context);
+                assert_eq!(assignment.len(),num_variables,"{}",context);
+                assert!(satisfies(&assignment,clauses),"{} assignment {:?} doesn't satisfy the clauses",context,assignment);
+            },
+            None => assert!(!expected,"{} found no assignment for a satisfiable formula",context),
+        }
+    }
+
+    fn check_algorithms<G: SearchGraph>(graph: &G, num_variables: usize, clauses: &[Clause], expected: bool, context: &str) {
+        let mut tarjan = Tarjan::new(graph);
+        tarjan.find_scc();
+        check_solution(&tarjan,num_variables,clauses,expected,&format!("{} tarjan",context));
+        let mut gabow = Gabow::new(graph);
+        gabow.find_scc();
+        check_solution(&gabow,num_variables,clauses,expected,&format!("{} gabow",context));
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut satisfiable_count = 0;
+        for seed in 0..CASES {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let text = random_formula(&mut rng);
+            let context = format!("seed {} formula {:?}",seed,text);
+
+            let formula = read_constraints(text.as_bytes(),ParseMode::Strict).unwrap();
+            let mut graph = DirectedGraph::new();
+            formula.build_graph(&mut graph);
+            let expected = brute_force(formula.num_variables,&formula.clauses).is_some();
+            if expected {
+                satisfiable_count += 1;
+            }
+
+            for use_iter in [true, false] {
+                let mut k = Kosaraju::new(&graph,use_iter);
+                k.find_scc();
+                check_solution(&k,formula.num_variables,&formula.clauses,expected,&format!("{} kosaraju iter {}",context,use_iter));
+            }
+            check_algorithms(&graph,formula.num_variables,&formula.clauses,expected,&context);
+
+            let mut csr = CsrGraph::new();
+            formula.build_graph(&mut csr);
+            check_algorithms(&csr,formula.num_variables,&formula.clauses,expected,&format!("{} csr",context));
+        }
+        // make sure the formulas cover both outcomes
+        assert!(satisfiable_count > CASES / 10 && satisfiable_count < CASES * 9 / 10,"{} of {} satisfiable",satisfiable_count,CASES);
+    }
+
+    #[test]
+    fn brute_force_reference() {
+        let clause = |first, second| Clause::new(lit(first),lit(second));
+        assert_eq!(brute_force(2,&[clause(1,2),clause(-1,2),clause(-2,1)]),Some(vec!(true,true)));
+        assert_eq!(brute_force(1,&[clause(1,1),clause(-1,-1)]),None);
+        assert_eq!(brute_force(3,&[]),Some(vec!(false,false,false)));
+        assert!(!satisfies(&[true],&[clause(-1,2)]));
+        assert_eq!(brute_force_solutions(2,&[clause(1,2)]).len(),3);
+    }
+}
@@ -2,21 +2,49 @@ extern crate clap;
 //use log::{ info , error /* ,debug, warn,trace */ };
 
 //use clap::{Arg, Command,arg, Parser, Subcommand};
-use clap::{Parser};
+use clap::{Parser,Subcommand,Args,ValueEnum};
+use sat2::scc::SccAlgorithm;
+use sat2::parse::InputFormat;
+use sat2::generator::GeneratorMode;
+use sat2::export::ExportFormat;
 
 
+/// How the result is printed
+#[derive(Debug,Clone,Copy,PartialEq,Eq,ValueEnum)]
+pub enum OutputFormat {
+    /// 1 or 0, followed by whatever was asked for
+    Text,
+    /// one JSON object with the result, the assignment, SCC statistics, the conflict, input statistics and timings
+    Json,
+}
+
 #[derive(Parser, Debug)]
 #[clap(name = "short")]
 #[clap(author = "Marvin Mednick")]
 #[clap(version = "1.0")]
 #[clap(about = "Traveling Salesman Path", long_about = "Dynamic Programming approach to TSP")]
+#[clap(subcommand_negates_reqs = true)]
 pub struct CommandArgs  {
 
-   #[clap(value_parser)]
-   pub filename: String,
+    #[clap(subcommand)]
+    pub command: Option<Commands>,
+
+   #[clap(value_parser, required=true)]
+   /// input files (- reads from stdin); .gz, .xz and .zst files are decompressed.  More than one
+   /// file, a directory or a glob pattern (e.g. 'tests/input_*.txt') solves them all as a batch
+   pub filenames: Vec<String>,
 
-    #[clap(short, long )]
-    input_format: Option<bool>,
+    #[clap(short, long, default_value_t=1)]
+    /// number of files solved at the same time in batch mode
+    pub jobs: usize,
+
+    #[clap(short, long, value_enum, default_value_t=InputFormat::Auto)]
+    /// format of the input file
+    pub input_format: InputFormat,
+
+    #[clap(long, takes_value=false)]
+    /// skips malformed lines and extends the variable count for out of range literals (reporting each as a warning) instead of stopping
+    pub lenient: bool,
 
     #[clap(short, long, takes_value=false)]
     /// Skips the first line of the file (e.g. first line has number of edges, vertexes)
@@ -50,7 +78,149 @@ pub struct CommandArgs  {
     /// displays the top 5 counts (or 0)
     pub use_orig: bool,
 
+    #[clap(short, long, takes_value=false)]
+    /// prints a satisfying assignment (e.g. v 1 -2 3 0) when the constraints can be met
+    pub assignment: bool,
+
+    #[clap(short, long, takes_value=false)]
+    /// explains why the constraints can't be met by listing the conflicting implication cycle
+    pub explain: bool,
+
+    #[clap(long)]
+    /// writes a minimal unsatisfiable subset of the clauses to the given file when the constraints can't be met
+    pub core: Option<String>,
+
+    #[clap(long, value_enum, default_value_t=SccAlgorithm::Kosaraju)]
+    /// algorithm used to find the strongly connected components
+    pub algorithm: SccAlgorithm,
+
+    #[clap(long, takes_value=false)]
+    /// uses the recursive depth first search for Kosaraju (runs with a 512MB stack)
+    pub recursive: bool,
+
+    #[clap(long, takes_value=false)]
+    /// logs the order Kosaraju explores the vertexes in to log/kosara_explored_in and log/kosara_explored_out
+    pub log_explored: bool,
+
+    #[clap(long, takes_value=false, conflicts_with_all=&["explain","core","show-graph","export"])]
+    /// uses the compact (CSR) implication graph, for very large instances
+    pub csr: bool,
+
+    #[clap(long, value_delimiter=',', allow_hyphen_values=true, conflicts_with_all=&["explain","core"])]
+    /// checks the constraints with the given literals assumed to be true (e.g. 3,-17)
+    pub assume: Vec<isize>,
+
+    #[clap(long, takes_value=false, conflicts_with_all=&["explain","core","assume","show-graph"])]
+    /// simplifies the clauses (duplicates, unit clauses, pure literals, equivalent literals) before building the graph
+    pub simplify: bool,
+
+    #[clap(long, takes_value=false, conflicts_with_all=&["explain","core","assume","simplify","backbone","csr","assignment","show-graph","recursive","export-scc","all-solutions"])]
+    /// prints the number of satisfying assignments
+    pub count_solutions: bool,
+
+    #[clap(long, default_value_t=1_000_000)]
+    /// stops counting solutions once a group of connected variables has this many
+    pub solution_limit: u64,
+
+    #[clap(long, value_name="N", conflicts_with_all=&["explain","core","assume","simplify","backbone","csr","assignment","show-graph","recursive","export-scc","count-solutions"])]
+    /// prints up to N satisfying assignments, one per line
+    pub all_solutions: Option<usize>,
+
+    #[clap(long, takes_value=false, conflicts_with_all=&["explain","core","assume","simplify"])]
+    /// prints the literals that are true in every solution (with the implications that force them) and the free variables
+    pub backbone: bool,
+
+    #[clap(long, value_name="FILE", conflicts_with_all=&["count-solutions","all-solutions"])]
+    /// writes the implication graph to the file (- for stdout) before solving
+    pub export: Option<String>,
+
+    #[clap(long, value_enum, requires="export")]
+    /// format of the exported graph (picked from the file extension if not given)
+    pub export_format: Option<ExportFormat>,
 
+    #[clap(long, takes_value=false, requires="export")]
+    /// colours the exported graph by SCC and highlights the x / -x pairs that are in the same SCC
+    pub export_scc: bool,
 
+    #[clap(long, value_enum, default_value_t=OutputFormat::Text,
+           conflicts_with_all=&["explain","assume","show-graph","count-solutions","all-solutions","backbone"])]
+    /// format of the result
+    pub format: OutputFormat,
 }
 
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Generates a random 2-SAT instance
+    Gen(GenArgs),
+    /// Simplifies a formula and writes it out, reporting what each rule removed
+    Simplify(SimplifyArgs),
+    /// Answers implication queries ('implies <a> <b>' or 'entails <a> <b>', one per line) about a formula
+    Query(QueryArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct GenArgs {
+
+    #[clap(short='n', long)]
+    /// number of variables
+    pub variables: usize,
+
+    #[clap(short='m', long)]
+    /// number of clauses (defaults to the number of variables, the 2-SAT threshold)
+    pub clauses: Option<usize>,
+
+    #[clap(long, value_enum, default_value_t=GeneratorMode::Random)]
+    /// kind of instance to generate
+    pub mode: GeneratorMode,
+
+    #[clap(short, long, default_value_t=0)]
+    /// seed for the random number generator; the same seed gives the same instance
+    pub seed: u64,
+
+    #[clap(long, default_value_t=5)]
+    /// number of implications in each half of the contradiction cycle for unsat instances
+    pub cycle_length: usize,
+
+    #[clap(long, takes_value=false)]
+    /// writes DIMACS CNF instead of the course format
+    pub dimacs: bool,
+
+    #[clap(short, long, default_value="-")]
+    /// output file (- for stdout); .gz, .xz and .zst files are compressed
+    pub output: String,
+}
+
+#[derive(Args, Debug)]
+pub struct SimplifyArgs {
+
+    /// the formula to simplify (- for stdin)
+    pub input: String,
+
+    #[clap(long, value_enum, default_value_t=InputFormat::Auto)]
+    /// format of the input file
+    pub input_format: InputFormat,
+
+    #[clap(long, takes_value=false)]
+    /// writes DIMACS CNF instead of the course format
+    pub dimacs: bool,
+
+    #[clap(short, long, default_value="-")]
+    /// output file (- for stdout); .gz, .xz and .zst files are compressed
+    pub output: String,
+}
+
+#[derive(Args, Debug)]
+pub struct QueryArgs {
+
+    /// the formula to query
+    pub formula: String,
+
+    /// file with the queries (- for stdin)
+    #[clap(default_value="-")]
+    pub queries: String,
+
+    #[clap(long, value_enum, default_value_t=InputFormat::Auto)]
+    /// format of the formula file
+    pub input_format: InputFormat,
+}
@@ -0,0 +1,176 @@
+use log::{ info, debug };
+use std::collections::{BTreeMap,HashMap};
+
+use crate::dirgraph::DirectedGraph;
+use crate::graphbuilder::GraphBuilder;
+use crate::scc::SccFinder;
+use crate::searchgraph::SearchGraph;
+
+
+/// The condensation of a graph, with each SCC contracted to a single node.
+///
+/// The nodes are numbered from 1 in topological order, so every edge goes from a lower
+/// node to a higher one.  There is one edge between two nodes however many edges there are
+/// between their SCCs, and its weight is that number.  Edges within an SCC are dropped
+pub struct Condensation {
+    graph: DirectedGraph,
+    /// the vertexes in each SCC (sorted), node i is entry i-1
+    members: Vec<Vec<isize>>,
+    node_of: HashMap<isize,isize>,
+}
+
+impl Condensation {
+
+    /// Contracts the SCCs found in the graph
+    pub fn new<G: SearchGraph>(graph: &G, k: &dyn SccFinder) -> Condensation {
+
+        // SCCs are ranked in reverse topological order
+        let mut groups = BTreeMap::<std::cmp::Reverse<usize>,isize>::new();
+        for vertex in graph.get_vertex_ids() {
+            if let (Some(group), Some(rank)) = (k.get_group(vertex), k.get_scc_rank(vertex)) {
+                groups.insert(std::cmp::Reverse(rank),*group);
+            }
+        }
+
+        let mut members = Vec::<Vec<isize>>::with_capacity(groups.len());
+        let mut node_of = HashMap::<isize,isize>::new();
+        for group in groups.values() {
+            let mut group_members = k.get_scc_group(*group);
+            group_members.sort();
+            let node = members.len() as isize + 1;
+            for vertex in &group_members {
+                node_of.insert(*vertex,node);
+            }
+            members.push(group_members);
+        }
+
+        let mut multiplicity = BTreeMap::<(isize,isize),i32>::new();
+        for vertex in graph.get_vertex_ids() {
+            for dest in graph.get_outgoing_vertex_ids(vertex) {
+                if let (Some(from), Some(to)) = (node_of.get(&vertex), node_of.get(&dest)) {
+                    if from != to {
+                        *multiplicity.entry((*from,*to)).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        let mut condensed = DirectedGraph::new();
+        let mut builder = &mut condensed;
+        for node in 1..=members.len() as isize {
+            builder.add_vertex(node);
+        }
+        for ((from, to), count) in &multiplicity {
+            debug!("Condensation edge {} -> {} with multiplicity {}",from,to,count);
+            builder.add_edge(*from,*to,*count);
+        }
+        builder.finish();
+        info!("Condensed {} vertexes into {} SCCs with {} edges",node_of.len(),members.len(),multiplicity.len());
+
+        Condensation { graph: condensed, members, node_of }
+    }
+
+    /// The condensed graph;  the weight of each edge is its multiplicity
+    pub fn graph(&self) -> &DirectedGraph {
+        &self.graph
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.members.len()
+    }
+
+    /// The nodes in topological order
+    pub fn nodes(&self) -> std::ops::RangeInclusive<isize> {
+        1..=self.members.len() as isize
+    }
+
+    /// Returns the node the vertex was contracted into
+    pub fn node_of(&self, vertex: isize) -> Option<isize> {
+        self.node_of.get(&vertex).cloned()
+    }
+
+    /// Returns the vertexes of the SCC contracted into the node
+    pub fn members(&self, node: isize) -> &[isize] {
+        match usize::try_from(node - 1) {
+            Ok(index) if index < self.members.len() => &self.members[index],
+            _ => &[],
+        }
+    }
+
+    /// The number of edges of the original graph from one node's SCC to the other's
+    pub fn multiplicity(&self, from: isize, to: isize) -> usize {
+        if self.members(from).is_empty() {
+            return 0;
+        }
+        self.graph.get_outgoing_connection_weight(from,to).unwrap_or(0) as usize
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::bruteforce::seeded_formula;
+    use crate::condensation::Condensation;
+    use crate::dirgraph::DirectedGraph;
+    use crate::generator::GeneratorMode;
+    use crate::graphbuilder::GraphBuilder;
+    use crate::kosaraju::Kosaraju;
+    use crate::scc::SccFinder;
+    use crate::tarjan::Tarjan;
+
+    #[test]
+    fn contract_sccs() {
+        // {1,2,3} -> {4,5} twice, {4,5} -> 6, and 7 on its own
+        let mut graph = DirectedGraph::new();
+        let mut builder = &mut graph;
+        for (from, to) in [(1,2),(2,3),(3,1),(1,4),(3,5),(4,5),(5,4),(5,6)] {
+            builder.add_edge(from,to,1);
+        }
+        builder.add_vertex(7);
+        let mut k = Kosaraju::new(&graph,true);
+        k.find_scc();
+        let condensation = Condensation::new(&graph,&k);
+
+        assert_eq!(condensation.node_count(),4);
+        let first = condensation.node_of(1).unwrap();
+        let second = condensation.node_of(4).unwrap();
+        let third = condensation.node_of(6).unwrap();
+        assert_eq!(condensation.members(first),&[1,2,3]);
+        assert_eq!(condensation.members(second),&[4,5]);
+        assert!(first < second && second < third);
+        assert_eq!(condensation.multiplicity(first,second),2);
+        assert_eq!(condensation.multiplicity(second,third),1);
+        assert_eq!(condensation.multiplicity(second,first),0);
+        assert_eq!(condensation.graph().get_outgoing_vertex_ids(first),vec!(second));
+        assert_eq!(condensation.members(0),&[] as &[isize]);
+    }
+
+    #[test]
+    fn topological_order() {
+        for seed in 0..50 {
+            let num_variables = 2 + (seed % 20) as usize;
+            let formula = seeded_formula(seed,GeneratorMode::Random,num_variables,num_variables*2);
+            let mut graph = DirectedGraph::new();
+            formula.build_graph(&mut graph);
+            let mut k = Tarjan::new(&graph);
+            k.find_scc();
+            let condensation = Condensation::new(&graph,&k);
+
+            assert_eq!(condensation.node_count(),k.scc_count(),"seed {}",seed);
+            let mut edges = 0;
+            for node in condensation.nodes() {
+                assert_eq!(condensation.members(node).len(),k.get_scc_group(*k.get_group(condensation.members(node)[0]).unwrap()).len());
+                for dest in condensation.graph().get_outgoing_vertex_ids(node) {
+                    assert!(node < dest,"seed {} edge {} -> {}",seed,node,dest);
+                    edges += condensation.multiplicity(node,dest);
+                }
+            }
+            // every edge of the original graph is either inside an SCC or counted once
+            let crossing = graph.get_vertex_ids().iter()
+                .flat_map(|vertex| graph.get_outgoing_vertex_ids(*vertex).into_iter().map(move |dest| (*vertex,dest)))
+                .filter(|(vertex, dest)| k.get_group(*vertex) != k.get_group(*dest))
+                .count();
+            assert_eq!(edges,crossing,"seed {}",seed);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use log::{ info, debug, trace };
+use std::collections::{HashMap,VecDeque};
+use crate::dirgraph::DirectedGraph;
+use crate::scc::SccFinder;
+use crate::parse::Formula;
+use crate::literal::Variable;
+
+
+/// One implication edge along a conflict path, along with the clause it came from
+#[derive(Debug,Clone,PartialEq)]
+pub struct ImplicationStep {
+    pub edge_id: usize,
+    pub source: isize,
+    pub dest: isize,
+    /// index into the formula's clauses for the clause that created the edge
+    pub clause: Option<usize>,
+}
+
+/// A variable that is in the same SCC as its negation, along with the implication
+/// paths x -> ... -> -x  and -x -> ... -> x that prove the constraints can't be met
+#[derive(Debug,Clone)]
+pub struct Conflict {
+    pub variable: Variable,
+    pub forward: Vec<ImplicationStep>,
+    pub backward: Vec<ImplicationStep>,
+}
+
+impl Conflict {
+
+    /// Returns the indexes of all of the clauses used by the conflict (without duplicates)
+    pub fn clause_indexes(&self) -> Vec<usize> {
+        let mut indexes : Vec<usize> = self.forward.iter().chain(self.backward.iter())
+            .filter_map(|step| step.clause)
+            .collect();
+        indexes.sort();
+        indexes.dedup();
+        indexes
+    }
+}
+
+
+/// Finds the shortest implication path (as a list of edge ids) from one vertex to another,
+/// only passing through vertexes for which in_scope returns true
+pub fn find_path<S>(graph: &DirectedGraph, from: isize, to: isize, in_scope: S) -> Option<Vec<usize>>
+where S: Fn(isize) -> bool,
+{
+    // maps each vertex reached to the edge used to reach it
+    let mut reached_by = HashMap::<isize,usize>::new();
+    let mut queue = VecDeque::<isize>::new();
+    queue.push_back(from);
+
+    let mut found = false;
+    while let Some(vertex) = queue.pop_front() {
+        if vertex == to {
+            found = true;
+            break;
+        }
+        for edge in graph.get_outgoing_edges(vertex) {
+            let dest = edge.dest();
+            if dest != from && !reached_by.contains_key(&dest) && in_scope(dest) {
+                trace!("Path search reached {} from {} via edge {}",dest,vertex,edge.id());
+                reached_by.insert(dest,edge.id());
+                queue.push_back(dest);
+            }
+        }
+    }
+    if !found {
+        return None;
+    }
+
+    // walk back from the destination to get the list of edges
+    let mut path = Vec::<usize>::new();
+    let mut current = to;
+    while current != from {
+        let edge_id = reached_by[&current];
+        path.push(edge_id);
+        current = graph.get_edge_from_id(edge_id).unwrap().source();
+    }
+    path.reverse();
+    Some(path)
+}
+
+fn path_steps(graph: &DirectedGraph, formula: &Formula, path: &[usize]) -> Vec<ImplicationStep> {
+    path.iter().map(|edge_id| {
+        let edge = graph.get_edge_from_id(*edge_id).unwrap();
+        ImplicationStep {
+            edge_id: *edge_id,
+            source: edge.source(),
+            dest: edge.dest(),
+            clause: formula.edge_clause.get(edge_id).cloned(),
+        }
+    }).collect()
+}
+
+/// Finds a variable that ended up in the same SCC as its negation and extracts the two
+/// implication paths between them.  Returns None if there is no such variable.  The steps
+/// only name their clauses if the graph was built with build_graph_with_provenance
+pub fn find_conflict(graph: &DirectedGraph, k: &dyn SccFinder, formula: &Formula) -> Option<Conflict> {
+
+    for index in 0..formula.num_variables {
+        let variable = Variable::from_index(index);
+        let vertex_id = variable.positive().value();
+        let not_vertex_id = variable.negative().value();
+        let group = match k.get_group(vertex_id) {
+            Some(group) => *group,
+            None => continue,
+        };
+        if k.get_group(not_vertex_id) != Some(&group) {
+            continue;
+        }
+
+        info!("Variable {} and its negation are both in SCC {}",vertex_id,group);
+        // both paths stay inside the shared SCC
+        let in_scc = |v: isize| k.get_group(v) == Some(&group);
+        let forward = find_path(graph,vertex_id,not_vertex_id,in_scc)?;
+        let backward = find_path(graph,not_vertex_id,vertex_id,in_scc)?;
+        debug!("Conflict paths {:?} {:?}",forward,backward);
+        return Some(Conflict {
+            variable,
+            forward: path_steps(graph,formula,&forward),
+            backward: path_steps(graph,formula,&backward),
+        });
+    }
+    None
+}
+
+/// Produces a human readable explanation of a conflict, one line per entry
+pub fn describe_conflict(conflict: &Conflict, formula: &Formula) -> Vec<String> {
+
+    let mut lines = Vec::<String>::new();
+    let (var, not_var) = (conflict.variable.positive(), conflict.variable.negative());
+    lines.push(format!("Variable {} can not be assigned: {} implies {} and {} implies {}",conflict.variable,var,not_var,not_var,var));
+    for (from, to, path) in [(var, not_var, &conflict.forward), (not_var, var, &conflict.backward)] {
+        lines.push(format!("  {} -> {}:",from,to));
+        for step in path.iter() {
+            match step.clause {
+                Some(index) => lines.push(format!("    {} -> {}  from clause {} at {}",
+                                    step.source,step.dest,formula.clauses[index],formula.clause_source(index))),
+                None => lines.push(format!("    {} -> {}  (edge {})",step.source,step.dest,step.edge_id)),
+            }
+        }
+    }
+    lines
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::conflict::{describe_conflict,find_conflict,find_path,ImplicationStep};
+    use crate::dirgraph::DirectedGraph;
+    use crate::kosaraju::Kosaraju;
+    use crate::parse::{parse_formula,Formula,InputFormat,ParseMode};
+    use crate::scc::SccFinder;
+
+    /// Checks that the steps are a chain of edges of the graph from one vertex to another,
+    /// each created by the clause it names, and stay in the SCC
+    fn check_path(graph: &DirectedGraph, k: &dyn SccFinder, formula: &Formula, path: &[ImplicationStep], from: isize, to: isize) {
+        assert_eq!(path.first().map(|step| step.source),Some(from));
+        assert_eq!(path.last().map(|step| step.dest),Some(to));
+        for pair in path.windows(2) {
+            assert_eq!(pair[0].dest,pair[1].source);
+        }
+        for step in path {
+            let edge = graph.get_edge_from_id(step.edge_id).unwrap();
+            assert_eq!((edge.source(),edge.dest()),(step.source,step.dest));
+            assert_eq!(k.get_group(step.dest),k.get_group(from));
+            let clause = &formula.clauses[step.clause.unwrap()];
+            let implications = [(-clause.first.value(),clause.second.value()), (-clause.second.value(),clause.first.value())];
+            assert!(implications.contains(&(step.source,step.dest)),"{:?} isn't from clause {}",step,clause);
+        }
+    }
+
+    #[test]
+    fn conflict_paths() {
+        // 1 -> 2 -> -1 and -1 -> 3 -> 1, with 4 and 5 outside the conflict
+        let input = "5\n-1 2  # first\n-2 -1  # second\n1 3\n-3 1  # fourth\n4 5\n-4 5\n";
+        let mut formula = parse_formula(input.as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        formula.source = Some("unsat.txt".to_string());
+        let mut graph = DirectedGraph::new();
+        formula.build_graph_with_provenance(&mut graph);
+        let mut k = Kosaraju::new(&graph,true);
+        k.find_scc();
+
+        let conflict = find_conflict(&graph,&k,&formula).unwrap();
+        assert_eq!(conflict.variable.number(),1);
+        check_path(&graph,&k,&formula,&conflict.forward,1,-1);
+        check_path(&graph,&k,&formula,&conflict.backward,-1,1);
+        assert_eq!(conflict.clause_indexes(),vec!(0,1,2,3));
+
+        let lines = describe_conflict(&conflict,&formula);
+        assert_eq!(lines[0],"Variable 1 can not be assigned: 1 implies -1 and -1 implies 1");
+        assert!(lines.contains(&"    1 -> 2  from clause (-1 2) at unsat.txt line 2 (first)".to_string()));
+        assert!(lines.contains(&"    2 -> -1  from clause (-2 -1) at unsat.txt line 3 (second)".to_string()));
+        assert!(lines.contains(&"    -1 -> 3  from clause (1 3) at unsat.txt line 4".to_string()));
+        assert!(lines.contains(&"    3 -> 1  from clause (-3 1) at unsat.txt line 5 (fourth)".to_string()));
+
+        // the path search keeps to the vertexes in scope
+        assert_eq!(find_path(&graph,-4,5,|_| true).map(|path| path.len()),Some(1));
+        assert_eq!(find_path(&graph,1,-1,|vertex| vertex.abs() != 2),None);
+
+        let formula = parse_formula("3\n-1 2\n-2 3\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let mut graph = DirectedGraph::new();
+        formula.build_graph(&mut graph);
+        let mut k = Kosaraju::new(&graph,true);
+        k.find_scc();
+        assert!(find_conflict(&graph,&k,&formula).is_none());
+    }
+}
@@ -0,0 +1,236 @@
+use log::{ info, error, trace };
+
+use crate::graphbuilder::GraphBuilder;
+use crate::literal::Literal;
+use crate::searchgraph::SearchGraph;
+
+
+/// Compact implication graph stored in compressed sparse row form.
+///
+/// The vertexes are the literals -n..-1 and 1..n, stored densely by `Literal::index`
+/// (variable v at index 2*(v-1) and its negation at 2*(v-1)+1).  Edges are collected by the GraphBuilder
+/// interface and the row arrays are built when finish() is called, so edges added after
+/// that aren't visible until finish() is called again.  The collected edges are freed once
+/// they are in the row arrays.  Edge weights are not stored.
+#[derive(Debug,Clone,Default)]
+pub struct CsrGraph {
+    num_variables: usize,
+    num_edges: usize,
+    /// edges (as source index, dest index) added since the row arrays were last built
+    edges: Vec<(u32,u32)>,
+    /// outgoing edges of vertex i are out_targets[out_offsets[i]..out_offsets[i+1]]
+    out_offsets: Vec<usize>,
+    out_targets: Vec<u32>,
+    /// incoming edges of vertex i are in_sources[in_offsets[i]..in_offsets[i+1]]
+    in_offsets: Vec<usize>,
+    in_sources: Vec<u32>,
+}
+
+
+/// The largest variable whose literals have indexes that fit in the u32 row arrays
+const MAX_VARIABLE : usize = 1 << 31;
+
+/// Converts a vertex id to its dense index.  Vertex 0 isn't a literal, so it has no index
+fn vertex_index(vertex: isize) -> Option<usize> {
+    Literal::new(vertex).ok().map(Literal::index)
+}
+
+/// Converts a dense index back to its vertex id
+fn index_vertex(index: usize) -> isize {
+    Literal::from_index(index).value()
+}
+
+
+impl GraphBuilder for &mut CsrGraph {
+
+	fn add_edge(&mut self, source: isize, dest: isize, _weight: i32) -> Option<usize> {
+        let source_index = u32::try_from(vertex_index(source)?).ok();
+        let dest_index = u32::try_from(vertex_index(dest)?).ok();
+        let (source_index, dest_index) = match (source_index, dest_index) {
+            (Some(source_index), Some(dest_index)) => (source_index, dest_index),
+            _ => {
+                error!("CSR edge from {} to {} is past the largest variable {}",source,dest,MAX_VARIABLE);
+                return None;
+            },
+        };
+        self.add_vertex(source);
+        self.add_vertex(dest);
+        self.edges.push((source_index, dest_index));
+        self.num_edges += 1;
+        trace!("CSR edge {} from {} to {}",self.num_edges,source,dest);
+        Some(self.num_edges)
+    }
+
+    fn add_vertex(&mut self, id:  isize) {
+        let var = id.unsigned_abs();
+        if var > MAX_VARIABLE {
+            error!("CSR vertex {} is past the largest variable {}",id,MAX_VARIABLE);
+        }
+        else if var > self.num_variables {
+            self.num_variables = var;
+        }
+    }
+
+    fn reserve(&mut self, num_vertexes: usize, num_edges: usize) {
+        self.add_vertex((num_vertexes / 2).min(MAX_VARIABLE) as isize);
+        self.edges.reserve(num_edges);
+    }
+
+    fn finish(&mut self) {
+        self.build();
+    }
+}
+
+
+impl SearchGraph for CsrGraph {
+
+	fn get_vertex_ids(&self) -> Vec<isize> {
+        (0..self.vertex_count()).map(index_vertex).collect()
+    }
+
+	fn get_outgoing_vertex_ids(&self, vertex: isize) -> Vec<isize> {
+        match vertex_index(vertex) {
+            Some(index) => self.outgoing(index).iter().map(|index| index_vertex(*index as usize)).collect(),
+            None => Vec::<isize>::new(),
+        }
+    }
+
+	fn get_incoming_vertex_ids(&self, vertex: isize) -> Vec<isize> {
+        match vertex_index(vertex) {
+            Some(index) => self.incoming(index).iter().map(|index| index_vertex(*index as usize)).collect(),
+            None => Vec::<isize>::new(),
+        }
+    }
+
+    fn dense_vertex_count(&self) -> Option<usize> {
+        Some(self.vertex_count())
+    }
+
+    fn dense_index(&self, vertex: isize) -> Option<usize> {
+        vertex_index(vertex)
+    }
+}
+
+
+impl CsrGraph {
+
+    pub fn new() -> CsrGraph {
+        CsrGraph::default()
+    }
+
+    /// Fills in an offsets array (counting sort by key) and returns the values sorted by key
+    fn build_rows(edges: &[(u32,u32)], num_vertex: usize, key_value: impl Fn(&(u32,u32)) -> (u32,u32)) -> (Vec<usize>,Vec<u32>) {
+
+        let mut offsets = vec![0usize; num_vertex+1];
+        for edge in edges {
+            let (key, _) = key_value(edge);
+            offsets[key as usize + 1] += 1;
+        }
+        for i in 0..num_vertex {
+            offsets[i+1] += offsets[i];
+        }
+
+        let mut next = offsets.clone();
+        let mut values = vec![0u32; edges.len()];
+        for edge in edges {
+            let (key, value) = key_value(edge);
+            values[next[key as usize]] = value;
+            next[key as usize] += 1;
+        }
+        (offsets,values)
+    }
+
+    /// Builds the outgoing and incoming row arrays from the edges added so far, along with
+    /// the edges already in the rows
+    pub fn build(&mut self) {
+        let num_vertex = self.vertex_count();
+        let mut edges = Vec::<(u32,u32)>::with_capacity(self.out_targets.len() + self.edges.len());
+        for index in 0..self.out_offsets.len().saturating_sub(1) {
+            edges.extend(self.outgoing(index).iter().map(|dest| (index as u32, *dest)));
+        }
+        edges.append(&mut std::mem::take(&mut self.edges));
+
+        // free the old rows before the new ones are allocated
+        self.out_targets = Vec::new();
+        self.in_sources = Vec::new();
+        let (out_offsets, out_targets) = CsrGraph::build_rows(&edges, num_vertex, |(source,dest)| (*source,*dest));
+        self.out_offsets = out_offsets;
+        self.out_targets = out_targets;
+        let (in_offsets, in_sources) = CsrGraph::build_rows(&edges, num_vertex, |(source,dest)| (*dest,*source));
+        self.in_offsets = in_offsets;
+        self.in_sources = in_sources;
+        info!("Built CSR graph with {} vertexes and {} edges",num_vertex,edges.len());
+    }
+
+    /// Returns the dense indexes of the vertexes a vertex has edges to
+    pub fn outgoing(&self, index: usize) -> &[u32] {
+        if index + 1 >= self.out_offsets.len() {
+            return &[];
+        }
+        &self.out_targets[self.out_offsets[index]..self.out_offsets[index+1]]
+    }
+
+    /// Returns the dense indexes of the vertexes with edges to a vertex
+    pub fn incoming(&self, index: usize) -> &[u32] {
+        if index + 1 >= self.in_offsets.len() {
+            return &[];
+        }
+        &self.in_sources[self.in_offsets[index]..self.in_offsets[index+1]]
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.num_variables * 2
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.num_edges
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::csrgraph::{CsrGraph,vertex_index,index_vertex};
+    use crate::graphbuilder::GraphBuilder;
+    use crate::searchgraph::SearchGraph;
+
+    #[test]
+    fn literal_indexes() {
+        assert_eq!(vertex_index(1),Some(0));
+        assert_eq!(vertex_index(-1),Some(1));
+        assert_eq!(vertex_index(3),Some(4));
+        assert_eq!(vertex_index(-3),Some(5));
+        assert_eq!(vertex_index(0),None);
+        for index in 0..10 {
+            assert_eq!(vertex_index(index_vertex(index)),Some(index));
+        }
+    }
+
+    #[test]
+    fn build_rows() {
+        let mut graph = CsrGraph::new();
+        let mut g = &mut graph;
+        g.add_vertex(3);
+		assert_eq!(g.add_edge(-1,2,1),Some(1));
+		assert_eq!(g.add_edge(-2,1,1),Some(2));
+		assert_eq!(g.add_edge(-1,-3,1),Some(3));
+		assert_eq!(g.add_edge(0,1,1),None);
+        g.finish();
+        assert_eq!(graph.get_vertex_ids(),vec!(1,-1,2,-2,3,-3));
+        assert_eq!(graph.get_outgoing_vertex_ids(-1),vec!(2,-3));
+        assert_eq!(graph.get_incoming_vertex_ids(1),vec!(-2));
+        assert_eq!(graph.get_incoming_vertex_ids(-3),vec!(-1));
+        assert_eq!(graph.get_outgoing_vertex_ids(3),Vec::<isize>::new());
+        assert_eq!(graph.edge_count(),3);
+
+        // edges added after the rows are built are merged in by the next build
+        let mut g = &mut graph;
+        assert_eq!(g.add_edge(3,-1,1),Some(4));
+        assert_eq!(g.add_edge(1,1 << 40,1),None);
+        g.finish();
+        assert_eq!(graph.get_outgoing_vertex_ids(-1),vec!(2,-3));
+        assert_eq!(graph.get_incoming_vertex_ids(-1),vec!(3));
+        assert_eq!(graph.edge_count(),4);
+        assert_eq!(graph.vertex_count(),6);
+    }
+}
@@ -2,13 +2,14 @@
 //use std::cmp;
 use std::collections::{BTreeMap, BTreeSet};
 use std::io::Write;
-use log::{  info, error, debug, /*warn ,*/ trace };
+use log::{  info, error, debug, warn, trace };
 
 use std::fmt::Display; 
 use std::fmt;
 
 use crate::graphbuilder::GraphBuilder;
-use crate::LogFile;
+use crate::searchgraph::SearchGraph;
+use crate::log_files::LogFile;
 
 
 #[derive(Debug,Clone)]
@@ -40,6 +41,11 @@ impl Edge {
     }
 
 
+    /// Returns the id of the egde
+    pub fn id(&self) -> usize {
+        self.edge_id
+    }
+
     /// Returns the starting vertex of the egde
     pub fn source(&self) -> isize {
         self.source
@@ -155,10 +161,10 @@ impl GraphBuilder for &mut DirectedGraph {
 
 	fn add_edge(&mut self, v1: isize, v2: isize, weight: i32) -> Option<usize> {
 
-		//create the vertexes, if the don't exist
-		self.define_vertex(v1.clone());
-		self.define_vertex(v2.clone());
         if let Some (edge_id) = self.define_edge(v1.clone(),v2.clone(),weight) {
+            //create the vertexes, if the don't exist
+            self.define_vertex(v1.clone());
+            self.define_vertex(v2.clone());
             let v_map = &mut self.vertex_map;
 
             // add the edge to the first vertex's adjacency outgoing list
@@ -184,6 +190,22 @@ impl GraphBuilder for &mut DirectedGraph {
 }
 
 
+impl SearchGraph for DirectedGraph {
+
+	fn get_vertex_ids(&self) -> Vec<isize> {
+        DirectedGraph::get_vertex_ids(self)
+    }
+
+	fn get_outgoing_vertex_ids(&self, vertex: isize) -> Vec<isize> {
+        DirectedGraph::get_outgoing_vertex_ids(self,vertex)
+    }
+
+	fn get_incoming_vertex_ids(&self, vertex: isize) -> Vec<isize> {
+        DirectedGraph::get_incoming_vertex_ids(self,vertex)
+    }
+}
+
+
 impl DirectedGraph {
 	pub fn new() -> DirectedGraph {
 		let v_map = BTreeMap::<isize, Vertex>::new();
@@ -210,17 +232,18 @@ impl DirectedGraph {
     }
 
 	pub fn define_edge(&mut self, source: isize, dest: isize, weight: i32 ) -> Option<usize> {
-    //    if source != 0 && dest != 0 {
+        // vertex 0 would be the literal 0, which doesn't exist
+        if source != 0 && dest != 0 {
             let edge_id = self.next_edge_id.clone();
             self.next_edge_id += 1;
 			let e = Edge::new(edge_id, source, dest, weight);
 			self.edge_map.insert(edge_id,e);
             Some(edge_id)
-     //   }
-      //  else {
-       //     warn!("Invalid edge input 0  source {} dest {} weight {}", source, dest, weight);
-        //    None
-        //}
+        }
+        else {
+            warn!("Invalid edge input 0  source {} dest {} weight {}", source, dest, weight);
+            None
+        }
 	}
 
 
@@ -494,6 +517,8 @@ mod tests {
 		assert_eq!(g.define_vertex(1),Some(1));
 		assert_eq!((&mut g).add_edge(2,3,1),Some(1));
 		assert_eq!((&mut g).add_edge(1,2,1),Some(2));
+		assert_eq!((&mut g).add_edge(0,3,1),None);
+		assert_eq!(g.get_vertex_ids(),vec!(1,2,3));
     }
 
     fn test_init() -> DirectedGraph {
@@ -0,0 +1,514 @@
+use log::info;
+use std::fmt;
+use std::io::BufRead;
+use std::time::{Duration,Instant};
+
+use crate::assume::{solve_with_assumptions,AssumptionResult};
+use crate::backbone::find_backbone;
+use crate::batch::BatchResult;
+use crate::conflict::{find_conflict,describe_conflict};
+use crate::csrgraph::CsrGraph;
+use crate::dirgraph::DirectedGraph;
+use crate::enumerate::{count_solutions,Solutions};
+use crate::export::{ExportFormat,GraphExport};
+use crate::gabow::Gabow;
+use crate::graphbuilder::GraphBuilder;
+use crate::input::{open_input,create_output,InputReader};
+use crate::kosaraju::Kosaraju;
+use crate::literal::Literal;
+use crate::parse::{parse_formula,write_clauses,write_dimacs,Clause,Formula,InputFormat,ParseError,ParseMode};
+use crate::query::{Query,QueryEngine};
+use crate::report::Report;
+use crate::scc::{SccFinder,SccAlgorithm};
+use crate::searchgraph::SearchGraph;
+use crate::simplify::{simplify,Simplification};
+use crate::solver::{is_satisfiable,find_assignment,format_assignment};
+use crate::tarjan::Tarjan;
+use crate::unsatcore::find_unsat_core;
+
+
+/// Why a command couldn't be carried out;  the binary maps each kind to its exit code
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum RunError {
+    /// the options can't be used together
+    Usage(String),
+    Open(String),
+    Parse(String),
+    InvalidAssumption(String),
+    Write(String),
+}
+
+impl fmt::Display for RunError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunError::Usage(why) | RunError::Open(why) | RunError::Parse(why)
+                | RunError::InvalidAssumption(why) | RunError::Write(why) => write!(f, "{}", why),
+        }
+    }
+}
+
+fn open(name: &str) -> Result<InputReader,RunError> {
+    open_input(name).map_err(|why| RunError::Open(format!("couldn't open {}: {}",name,why)))
+}
+
+fn write_error(name: &str, why: std::io::Error) -> RunError {
+    RunError::Write(format!("couldn't write {}: {}",name,why))
+}
+
+
+/// The options used for reading and solving a formula
+#[derive(Debug,Clone,Copy)]
+pub struct SolveOptions {
+    pub input_format: InputFormat,
+    pub parse_mode: ParseMode,
+    pub algorithm: SccAlgorithm,
+    pub use_iter: bool,
+    pub use_csr: bool,
+    pub simplify: bool,
+    /// logs the order Kosaraju explores the vertexes in
+    pub log_explored: bool,
+}
+
+/// What to print for a single input file besides whether the constraints can be met
+#[derive(Debug,Clone)]
+pub struct RunOptions {
+    pub solve: SolveOptions,
+    pub show_assignment: bool,
+    pub explain: bool,
+    /// file to write a minimal unsatisfiable subset of the clauses to
+    pub core: Option<String>,
+    pub show_graph: bool,
+    pub count_solutions: bool,
+    pub solution_limit: u64,
+    pub all_solutions: Option<usize>,
+    pub backbone: bool,
+    pub export: Option<(String,ExportFormat)>,
+    pub export_scc: bool,
+    pub json: bool,
+    pub assumptions: Vec<Literal>,
+}
+
+
+/// Parses the input, simplifying the formula if asked to
+pub fn parse_input(input: InputReader, format: InputFormat, mode: ParseMode, simplify_formula: bool) -> Result<(Formula,Option<Simplification>),ParseError> {
+
+    let mut formula = parse_formula(input,format,mode)?;
+    let simplification = if simplify_formula {
+        let simplification = simplify(&mut formula);
+        for line in simplification.stats.report() {
+            info!("Simplify {}",line);
+        }
+        Some(simplification)
+    }
+    else {
+        None
+    };
+    Ok((formula,simplification))
+}
+
+/// Reads the named input into the graph, printing any warnings.  With provenance the formula
+/// keeps which clause each edge came from, which explaining a conflict needs
+fn read_formula<F: GraphBuilder>(name: &str, input: InputReader, format: InputFormat, mode: ParseMode,
+                                 simplify_formula: bool, provenance: bool, graph: F) -> Result<(Formula,Option<Simplification>),RunError> {
+
+    let (mut formula, simplification) = parse_input(input,format,mode,simplify_formula)
+        .map_err(|why| RunError::Parse(format!("Error reading input: {}",why)))?;
+    formula.source = Some(name.to_string());
+    for warning in &formula.warnings {
+        eprintln!("Warning: {}",warning);
+    }
+    if provenance {
+        formula.build_graph_with_provenance(graph);
+    }
+    else {
+        formula.build_graph(graph);
+    }
+    Ok((formula,simplification))
+}
+
+/// Creates the selected SCC algorithm for the graph and finds the SCCs
+pub fn find_scc<'a, G: SearchGraph>(graph: &'a G, algorithm: SccAlgorithm, use_iter: bool, log_explored: bool) -> Box<dyn SccFinder + 'a> {
+
+    let mut k : Box<dyn SccFinder> = match algorithm {
+        SccAlgorithm::Kosaraju => Box::new(Kosaraju::new(graph,use_iter).log_explored(log_explored)),
+        SccAlgorithm::Tarjan => Box::new(Tarjan::new(graph)),
+        SccAlgorithm::Gabow => Box::new(Gabow::new(graph)),
+    };
+    let start = Instant::now();
+    k.find_scc();
+    info!("{:?} found {} SCCs in {:?}",algorithm,k.scc_count(),start.elapsed());
+    k.log_scc_to_files("kosara");
+    // sort in reverse order
+    let mut scc_sizes = k.get_scc_sizes();
+    scc_sizes.sort_by(|a, b| b.cmp(a));
+    info!("K sizes {:?}",scc_sizes);
+    k
+}
+
+/// Prints 1 (and optionally the assignment) if the constraints can be met, otherwise 0.  If the
+/// formula was simplified the assignment is extended to cover the variables that were removed
+fn print_result(k: &dyn SccFinder, num_variables: usize, show_assignment: bool, simplification: Option<&Simplification>) -> bool {
+
+    match find_assignment(k,num_variables) {
+        Some(assignment) => {
+            println!("1");
+            if show_assignment {
+                let assignment = match simplification {
+                    Some(simplification) => simplification.extend_assignment(&assignment),
+                    None => assignment,
+                };
+                println!("{}",format_assignment(&assignment));
+            }
+            true
+        },
+        None => {
+            println!("0");
+            false
+        },
+    }
+}
+
+/// Creates the report printed for --format json, with the time taken to read the input and find the SCCs
+fn new_report(formula: &Formula, k: &dyn SccFinder, simplification: Option<&Simplification>, read_time: Duration, scc_time: Duration) -> Report {
+
+    let mut report = Report::new(formula,k);
+    if let Some(simplification) = simplification {
+        report.add_simplification(simplification);
+    }
+    report.add_timing("read",read_time);
+    report.add_timing("scc",scc_time);
+    report
+}
+
+/// Prints 1 (and optionally the assignment) if the constraints can be met with the
+/// assumed literals set to true, otherwise prints 0 and the assumptions that failed
+fn print_assumption_result<G: SearchGraph>(graph: &G, num_variables: usize, assumptions: &[Literal], show_assignment: bool) -> Result<(),RunError> {
+
+    match solve_with_assumptions(graph,num_variables,assumptions).map_err(RunError::InvalidAssumption)? {
+        AssumptionResult::Satisfiable(assignment) => {
+            println!("1");
+            if show_assignment {
+                println!("{}",format_assignment(&assignment));
+            }
+        },
+        AssumptionResult::Unsatisfiable(failed) => {
+            println!("0");
+            if failed.is_empty() {
+                println!("constraints can't be met even without the assumptions");
+            }
+            else {
+                let failed_text : Vec<String> = failed.iter().map(|literal| literal.to_string()).collect();
+                println!("failed assumptions: {}",failed_text.join(" "));
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Prints 1 and the backbone if the constraints can be met, otherwise 0.  The forced literals
+/// are listed first, then the free variables, then the implication chain for each forced literal
+fn print_backbone<G: SearchGraph>(graph: &G, k: &dyn SccFinder, num_variables: usize) {
+
+    let backbone = match find_backbone(graph,k,num_variables) {
+        Some(backbone) => backbone,
+        None => {
+            println!("0");
+            return;
+        },
+    };
+    println!("1");
+    let forced : Vec<String> = backbone.forced.iter().map(|forced| forced.literal.to_string()).collect();
+    let free : Vec<String> = backbone.free.iter().map(|var| var.to_string()).collect();
+    println!("backbone: {}",forced.join(" "));
+    println!("free: {}",free.join(" "));
+    for forced in &backbone.forced {
+        let chain : Vec<String> = forced.chain.iter().map(|literal| literal.to_string()).collect();
+        println!("{}: {}",forced.literal,chain.join(" -> "));
+    }
+}
+
+/// Writes the clauses to the named file (- for stdout), in DIMACS CNF or the course format
+pub fn write_formula(name: &str, num_variables: usize, clauses: &[Clause], dimacs: bool) -> Result<(),RunError> {
+
+    create_output(name).and_then(|mut out| {
+        if dimacs {
+            write_dimacs(&mut out,num_variables,clauses)?;
+        }
+        else {
+            write_clauses(&mut out,num_variables,clauses)?;
+        }
+        out.finish()
+    }).map_err(|why| write_error(name,why))
+}
+
+/// Simplifies a formula and writes it out, with what each rule removed on stderr
+pub fn simplify_file(input_name: &str, input_format: InputFormat, output_name: &str, dimacs: bool) -> Result<(),RunError> {
+
+    let (formula, simplification) = match parse_input(open(input_name)?,input_format,ParseMode::Strict,true) {
+        Ok((formula, Some(simplification))) => (formula,simplification),
+        Ok((_, None)) => unreachable!("the formula is always simplified"),
+        Err(why) => return Err(RunError::Parse(format!("Error reading input: {}",why))),
+    };
+    for line in simplification.stats.report() {
+        eprintln!("{}",line);
+    }
+    eprintln!("{} clauses left and {} of {} variables removed after {} rounds{}",formula.clauses.len(),
+              simplification.eliminated_count(),formula.num_variables,simplification.stats.rounds,
+              if simplification.unsatisfiable { " - the unit clauses can't be satisfied" } else { "" });
+    write_formula(output_name,formula.num_variables,&formula.clauses,dimacs)
+}
+
+/// Loads a formula and answers each of the queries read from a file (or stdin), printing
+/// yes along with the implications that show it, or no.  Lines that aren't valid queries
+/// are reported and skipped, and make the whole run fail once every query has been tried
+pub fn answer_queries(formula_name: &str, queries_name: &str, input_format: InputFormat) -> Result<(),RunError> {
+
+    if formula_name == "-" && queries_name == "-" {
+        return Err(RunError::Usage("the formula and the queries can't both be read from stdin".to_string()));
+    }
+    let mut graph = DirectedGraph::new();
+    let (formula, _) = read_formula(formula_name,open(formula_name)?,input_format,ParseMode::Strict,false,false,&mut graph)?;
+    let mut engine = QueryEngine::new(&graph,formula.num_variables);
+
+    let mut failed = 0;
+    for (index, line) in open(queries_name)?.lines().enumerate() {
+        let query = line.map_err(|why| why.to_string()).and_then(|line| Query::parse(&line)).and_then(|query| match query {
+            Some(query) if query.literals().iter().any(|literal| literal.var().number() > formula.num_variables) =>
+                Err(format!("{} uses a variable larger than the number of variables ({})",query,formula.num_variables)),
+            query => Ok(query),
+        });
+        match query {
+            Ok(Some(query)) => match engine.answer(&query) {
+                Some(witness) => println!("{}: yes ({})",query,witness),
+                None => println!("{}: no",query),
+            },
+            Ok(None) => {},
+            Err(why) => {
+                eprintln!("{} line {}: {}",queries_name,index+1,why);
+                failed += 1;
+            },
+        }
+    }
+    if failed > 0 {
+        return Err(RunError::Parse(format!("{} lines of {} couldn't be read as queries",failed,queries_name)));
+    }
+    Ok(())
+}
+
+/// Solves one file of a batch, returning whether the constraints can be met instead of printing it
+pub fn solve_file(name: &str, options: SolveOptions) -> BatchResult {
+
+    let start = Instant::now();
+    let input = match open_input(name) {
+        Ok(input) => input,
+        Err(why) => return BatchResult::failed(name,format!("couldn't open: {}",why),start.elapsed()),
+    };
+
+    let parsed = parse_input(input,options.input_format,options.parse_mode,options.simplify);
+    let solved = if options.use_csr {
+        let mut graph = CsrGraph::new();
+        parsed.map(|(formula, _)| {
+            formula.build_graph(&mut graph);
+            let k = find_scc(&graph,options.algorithm,options.use_iter,options.log_explored);
+            let satisfiable = is_satisfiable(k.as_ref(),formula.num_variables);
+            (formula, satisfiable)
+        })
+    }
+    else {
+        let mut graph = DirectedGraph::new();
+        parsed.map(|(formula, _)| {
+            formula.build_graph(&mut graph);
+            graph.log_graph("kosara");
+            let k = find_scc(&graph,options.algorithm,options.use_iter,options.log_explored);
+            let satisfiable = is_satisfiable(k.as_ref(),formula.num_variables);
+            (formula, satisfiable)
+        })
+    };
+
+    match solved {
+        Ok((formula, satisfiable)) => BatchResult {
+            name: name.to_string(),
+            outcome: Ok(satisfiable),
+            num_variables: formula.num_variables,
+            num_clauses: formula.clauses.len(),
+            elapsed: start.elapsed(),
+        },
+        Err(why) => BatchResult::failed(name,why.to_string(),start.elapsed()),
+    }
+}
+
+/// Counts or lists the solutions of the named input
+fn enumerate_file(name: &str, input: InputReader, options: &RunOptions) -> Result<(),RunError> {
+
+    let mut graph = CsrGraph::new();
+    let (formula, _) = read_formula(name,input,options.solve.input_format,options.solve.parse_mode,false,false,&mut graph)?;
+    if options.count_solutions {
+        println!("{}",count_solutions(&graph,formula.num_variables,options.solution_limit));
+    }
+    if let Some(max_solutions) = options.all_solutions {
+        for assignment in Solutions::new(&graph,formula.num_variables).take(max_solutions) {
+            println!("{}",format_assignment(&assignment));
+        }
+    }
+    Ok(())
+}
+
+/// Solves the named input with the compact graph, which can't explain a conflict
+fn run_csr(name: &str, input: InputReader, options: &RunOptions) -> Result<(),RunError> {
+
+    let solve = &options.solve;
+    let start = Instant::now();
+    let mut graph = CsrGraph::new();
+    let (formula, simplification) = read_formula(name,input,solve.input_format,solve.parse_mode,solve.simplify,false,&mut graph)?;
+    let read_time = start.elapsed();
+    info!("CSR graph has {} vertexes and {} edges",graph.vertex_count(),graph.edge_count());
+    if !options.assumptions.is_empty() {
+        return print_assumption_result(&graph,formula.num_variables,&options.assumptions,options.show_assignment);
+    }
+    let scc_start = Instant::now();
+    let k = find_scc(&graph,solve.algorithm,solve.use_iter,solve.log_explored);
+    let scc_time = scc_start.elapsed();
+    if options.backbone {
+        print_backbone(&graph,k.as_ref(),formula.num_variables);
+        return Ok(());
+    }
+    if options.json {
+        // finding the conflict paths needs the full graph, so it's left out
+        let mut report = new_report(&formula,k.as_ref(),simplification.as_ref(),read_time,scc_time);
+        if !report.satisfiable {
+            report.skip_conflict();
+        }
+        report.add_timing("total",start.elapsed());
+        println!("{}",report.to_json());
+        return Ok(());
+    }
+    print_result(k.as_ref(),formula.num_variables,options.show_assignment,simplification.as_ref());
+    Ok(())
+}
+
+/// Reads and solves a single input file (- for stdin), printing the result and whatever
+/// else the options ask for
+pub fn run(file: &str, options: &RunOptions) -> Result<(),RunError> {
+
+    let input = open(file)?;
+    let name = if file == "-" { "stdin" } else { file };
+    if options.count_solutions || options.all_solutions.is_some() {
+        return enumerate_file(name,input,options);
+    }
+    if options.solve.use_csr {
+        return run_csr(name,input,options);
+    }
+
+    let solve = &options.solve;
+    let start = Instant::now();
+    // only the conflict explanation, the core and the json report name the clauses behind the edges
+    let provenance = options.explain || options.core.is_some() || options.json;
+    let mut graph = DirectedGraph::new();
+    let (formula, simplification) = read_formula(name,input,solve.input_format,solve.parse_mode,solve.simplify,provenance,&mut graph)?;
+    let read_time = start.elapsed();
+    let num_variables = formula.num_variables;
+
+    if options.show_graph {
+        println!("DirGraph");
+        graph.print_graph();
+        return Ok(());
+    }
+    graph.log_graph("kosara");
+
+    if let Some((export_file, export_format)) = &options.export {
+        let k = if options.export_scc { Some(find_scc(&graph,solve.algorithm,solve.use_iter,solve.log_explored)) } else { None };
+        let mut exporter = GraphExport::new(&graph).highlight_conflicts(options.export_scc);
+        if let Some(k) = &k {
+            exporter = exporter.scc(k.as_ref());
+        }
+        create_output(export_file).and_then(|mut out| exporter.write(*export_format,&mut out).and_then(|_| out.finish()))
+            .map_err(|why| write_error(export_file,why))?;
+        info!("Exported the graph to {} as {:?}",export_file,export_format);
+    }
+
+    if !options.assumptions.is_empty() {
+        return print_assumption_result(&graph,num_variables,&options.assumptions,options.show_assignment);
+    }
+
+    let scc_start = Instant::now();
+    let k = find_scc(&graph,solve.algorithm,solve.use_iter,solve.log_explored);
+    let scc_time = scc_start.elapsed();
+    if options.backbone {
+        print_backbone(&graph,k.as_ref(),num_variables);
+        return Ok(());
+    }
+    let satisfiable = if options.json {
+        let mut report = new_report(&formula,k.as_ref(),simplification.as_ref(),read_time,scc_time);
+        if !report.satisfiable {
+            let conflict_start = Instant::now();
+            if let Some(conflict) = find_conflict(&graph,k.as_ref(),&formula) {
+                report.add_conflict(&conflict,&formula);
+            }
+            report.add_timing("conflict",conflict_start.elapsed());
+        }
+        report.add_timing("total",start.elapsed());
+        println!("{}",report.to_json());
+        report.satisfiable
+    }
+    else {
+        print_result(k.as_ref(),num_variables,options.show_assignment,simplification.as_ref())
+    };
+    if satisfiable {
+        return Ok(());
+    }
+    if options.explain {
+        if let Some(conflict) = find_conflict(&graph,k.as_ref(),&formula) {
+            for line in describe_conflict(&conflict,&formula) {
+                println!("{}",line);
+            }
+        }
+    }
+    if let Some(core_file) = &options.core {
+        let core = find_unsat_core(&graph,k.as_ref(),&formula).unwrap_or_default();
+        write_formula(core_file,num_variables,&core,false)?;
+        info!("Wrote {} core clauses to {}",core.len(),core_file);
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::batch::result_bits;
+    use crate::driver::{answer_queries,solve_file,RunError,SolveOptions};
+    use crate::log_files::set_thread_log_dir;
+    use crate::parse::{InputFormat,ParseMode};
+    use crate::scc::SccAlgorithm;
+
+    #[test]
+    fn solve_and_fail() {
+        let dir = std::env::temp_dir().join("sat2_driver_test");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let sat = dir.join("sat.txt");
+        let unsat = dir.join("unsat.txt");
+        let bad = dir.join("bad.txt");
+        std::fs::write(&sat,"2\n1 2\n-1 2\n").unwrap();
+        std::fs::write(&unsat,"1\n1 1\n-1 -1\n").unwrap();
+        std::fs::write(&bad,"2\n1 x\n").unwrap();
+        set_thread_log_dir(Some(&dir.join("log").display().to_string()));
+
+        let mut options = SolveOptions { input_format: InputFormat::Auto, parse_mode: ParseMode::Strict, algorithm: SccAlgorithm::Tarjan,
+                                         use_iter: true, use_csr: false, simplify: false, log_explored: false };
+        let names = [&sat,&unsat,&bad,&dir.join("missing.txt")].map(|path| path.display().to_string());
+        for use_csr in [false,true] {
+            options.use_csr = use_csr;
+            let results : Vec<_> = names.iter().map(|name| solve_file(name,options)).collect();
+            assert_eq!(result_bits(&results),"10??");
+            assert_eq!((results[0].num_variables,results[0].num_clauses),(2,2));
+        }
+
+        let sat_name = sat.display().to_string();
+        assert!(matches!(answer_queries("-","-",InputFormat::Auto),Err(RunError::Usage(_))));
+        assert!(matches!(answer_queries(&names[3],&sat_name,InputFormat::Auto),Err(RunError::Open(_))));
+        assert!(matches!(answer_queries(&names[2],&sat_name,InputFormat::Auto),Err(RunError::Parse(_))));
+        set_thread_log_dir(None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
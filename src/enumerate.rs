@@ -0,0 +1,335 @@
+use log::{ info, debug };
+
+use crate::literal::{Literal,Variable};
+use crate::searchgraph::SearchGraph;
+use crate::scc::SccFinder;
+use crate::solver::is_satisfiable;
+use crate::tarjan::Tarjan;
+
+
+/// Depth first search over the values of a list of variables.  Each value is propagated
+/// through the implication graph, so the assignment is always closed under implication.
+///
+/// For a satisfiable formula a closed assignment without a conflict can always be extended to
+/// a solution, so when one value of a variable leads to a conflict the other one can't, and
+/// the search never has to back out of a dead end.  Each solution is found in polynomial time
+struct Search<'a, G: SearchGraph> {
+    graph: &'a G,
+    /// the variables to decide, in the order they're decided
+    order: Vec<Variable>,
+    values: Vec<Option<bool>>,
+    /// the variables set so far, in the order they were set
+    trail: Vec<Variable>,
+    /// position in the order, length of the trail before the decision, and the value decided
+    decisions: Vec<(usize,usize,bool)>,
+}
+
+impl<'a, G: SearchGraph> Search<'a, G> {
+
+    fn new(graph: &'a G, num_variables: usize, order: Vec<Variable>) -> Search<'a, G> {
+        Search { graph, order, values: vec![None; num_variables], trail: Vec::<Variable>::new(), decisions: Vec::new() }
+    }
+
+    /// Sets the literal to true along with everything it implies.  On a conflict the
+    /// values are left for the caller to undo
+    fn propagate(&mut self, literal: Literal) -> bool {
+        let mut stack = vec!(literal);
+        while let Some(literal) = stack.pop() {
+            let var = literal.var();
+            match self.values[var.index()] {
+                Some(value) if value == literal.is_positive() => continue,
+                Some(_) => return false,
+                None => {},
+            }
+            self.values[var.index()] = Some(literal.is_positive());
+            self.trail.push(var);
+            for dest in self.graph.get_outgoing_vertex_ids(literal.value()) {
+                if let Ok(dest) = Literal::new(dest) {
+                    stack.push(dest);
+                }
+            }
+        }
+        true
+    }
+
+    /// Clears every value and decision and starts again with a new order of variables
+    fn restart(&mut self, order: Vec<Variable>) {
+        self.undo(0);
+        self.decisions.clear();
+        self.order = order;
+    }
+
+    fn undo(&mut self, trail_length: usize) {
+        for var in self.trail.drain(trail_length..) {
+            self.values[var.index()] = None;
+        }
+    }
+
+    /// Decides the remaining variables from the position in the order, trying true first
+    fn descend(&mut self, mut position: usize) -> bool {
+        while position < self.order.len() {
+            let var = self.order[position];
+            if self.values[var.index()].is_none() {
+                let trail_length = self.trail.len();
+                let value = if self.propagate(var.positive()) {
+                    true
+                }
+                else {
+                    self.undo(trail_length);
+                    if !self.propagate(var.negative()) {
+                        // only possible if the formula can't be satisfied
+                        self.undo(trail_length);
+                        return false;
+                    }
+                    false
+                };
+                self.decisions.push((position,trail_length,value));
+            }
+            position += 1;
+        }
+        true
+    }
+
+    /// Goes back to the last decision that was true and tries false instead.  Returns false
+    /// once every decision has been tried both ways
+    fn backtrack(&mut self) -> bool {
+        while let Some((position, trail_length, value)) = self.decisions.pop() {
+            self.undo(trail_length);
+            if value && self.propagate(self.order[position].negative()) {
+                self.decisions.push((position,trail_length,false));
+                if self.descend(position+1) {
+                    return true;
+                }
+            }
+            else {
+                self.undo(trail_length);
+            }
+        }
+        false
+    }
+
+    /// The current assignment;  variables that haven't been set are false
+    fn values(&self) -> Vec<bool> {
+        self.values.iter().map(|value| value.unwrap_or(false)).collect()
+    }
+}
+
+
+/// Lazily produces every satisfying assignment of an implication graph.  Entry i of each
+/// assignment is the value of variable i+1.
+///
+/// The variables are decided in topological order of the SCC condensation, so that the
+/// literals that imply the most are decided first
+pub struct Solutions<'a, G: SearchGraph> {
+    search: Search<'a, G>,
+    started: bool,
+    finished: bool,
+}
+
+impl<'a, G: SearchGraph> Solutions<'a, G> {
+
+    pub fn new(graph: &'a G, num_variables: usize) -> Solutions<'a, G> {
+        let mut k = Tarjan::new(graph);
+        k.find_scc();
+        let finished = !is_satisfiable(&k,num_variables);
+
+        // SCCs are ranked in reverse topological order, so the highest rank comes first
+        let mut order : Vec<Variable> = (0..num_variables).map(Variable::from_index).collect();
+        let rank = |var: &Variable| k.get_scc_rank(var.positive().value()).max(k.get_scc_rank(var.negative().value()));
+        order.sort_by_key(|var| std::cmp::Reverse(rank(var)));
+        debug!("Enumerating {} variables in the order {:?}",num_variables,order);
+
+        Solutions { search: Search::new(graph,num_variables,order), started: false, finished }
+    }
+}
+
+impl<'a, G: SearchGraph> Iterator for Solutions<'a, G> {
+    type Item = Vec<bool>;
+
+    fn next(&mut self) -> Option<Vec<bool>> {
+        if self.finished {
+            return None;
+        }
+        let found = if self.started { self.search.backtrack() } else { self.search.descend(0) };
+        self.started = true;
+        if !found {
+            self.finished = true;
+            return None;
+        }
+        Some(self.search.values())
+    }
+}
+
+
+/// Finds a solution that differs from the given one on as many variables as it greedily can,
+/// deciding the variables in the order given.  Any variable whose value differs between the
+/// two can be either true or false.  Returns None if the formula can't be satisfied
+pub fn differing_solution<G: SearchGraph>(graph: &G, assignment: &[bool], order: Vec<Variable>) -> Option<Vec<bool>> {
+
+    let mut search = Search::new(graph,assignment.len(),Vec::new());
+    for var in order {
+        if search.values[var.index()].is_some() {
+            continue;
+        }
+        let value = assignment[var.index()];
+        let trail_length = search.trail.len();
+        if !search.propagate(var.literal(!value)) {
+            search.undo(trail_length);
+            if !search.propagate(var.literal(value)) {
+                return None;
+            }
+        }
+    }
+    Some(search.values())
+}
+
+
+/// The number of satisfying assignments
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum SolutionCount {
+    Exact(u128),
+    /// counting stopped at the limit, so there are at least this many
+    AtLeast(u128),
+    /// more than fit in a u128
+    TooMany,
+}
+
+impl std::fmt::Display for SolutionCount {
+
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SolutionCount::Exact(count) => write!(f, "{}", count),
+            SolutionCount::AtLeast(count) => write!(f, "at least {}", count),
+            SolutionCount::TooMany => write!(f, "more than {}", u128::MAX),
+        }
+    }
+}
+
+/// Groups the variables that are connected through the implication graph;  the
+/// solutions of each group don't depend on the others
+fn independent_groups<G: SearchGraph>(graph: &G, num_variables: usize) -> Vec<Vec<Variable>> {
+    let mut group_of : Vec<Option<usize>> = vec![None; num_variables];
+    let mut groups = Vec::<Vec<Variable>>::new();
+    for index in 0..num_variables {
+        if group_of[index].is_some() {
+            continue;
+        }
+        let group = groups.len();
+        let mut members = vec!(Variable::from_index(index));
+        group_of[index] = Some(group);
+        let mut next = 0;
+        while next < members.len() {
+            let var = members[next];
+            next += 1;
+            for literal in [var.positive(), var.negative()] {
+                let neighbours = graph.get_outgoing_vertex_ids(literal.value()).into_iter()
+                    .chain(graph.get_incoming_vertex_ids(literal.value()));
+                for neighbour in neighbours.filter_map(|vertex| Literal::new(vertex).ok()).map(Literal::var) {
+                    if neighbour.index() < num_variables && group_of[neighbour.index()].is_none() {
+                        group_of[neighbour.index()] = Some(group);
+                        members.push(neighbour);
+                    }
+                }
+            }
+        }
+        groups.push(members);
+    }
+    groups
+}
+
+/// Counts the satisfying assignments of an implication graph.  The variables are split into
+/// independent groups and the counts of the groups are multiplied, so a variable that isn't in
+/// any clause just doubles the count.  The solutions of a group are enumerated one by one, so
+/// the count stops being exact once any group has `limit` solutions
+pub fn count_solutions<G: SearchGraph>(graph: &G, num_variables: usize, limit: u64) -> SolutionCount {
+
+    let mut k = Tarjan::new(graph);
+    k.find_scc();
+    if !is_satisfiable(&k,num_variables) {
+        return SolutionCount::Exact(0);
+    }
+
+    let mut total : u128 = 1;
+    let mut exact = true;
+    let groups = independent_groups(graph,num_variables);
+    let group_count = groups.len();
+    // one search is shared by the groups, as each only sets its own variables
+    let mut search = Search::new(graph,num_variables,Vec::new());
+    for group in groups {
+        search.restart(group);
+        let mut count : u64 = 0;
+        let mut found = search.descend(0);
+        while found && count < limit {
+            count += 1;
+            found = search.backtrack();
+        }
+        if found {
+            exact = false;
+        }
+        total = match total.checked_mul(count as u128) {
+            Some(total) => total,
+            None => {
+                info!("More than {} solutions over {} independent groups of variables",u128::MAX,group_count);
+                return SolutionCount::TooMany;
+            },
+        };
+    }
+    info!("Counted {} solutions over {} independent groups of variables (exact {})",total,group_count,exact);
+    if exact { SolutionCount::Exact(total) } else { SolutionCount::AtLeast(total) }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::bruteforce::{brute_force_solutions,satisfies,seeded_formula};
+    use crate::csrgraph::CsrGraph;
+    use crate::dirgraph::DirectedGraph;
+    use crate::enumerate::{count_solutions,Solutions,SolutionCount};
+    use crate::generator::GeneratorMode;
+    use crate::parse::{parse_formula,Formula,InputFormat,ParseMode};
+
+    #[test]
+    fn enumerate_solutions() {
+        let formula = parse_formula("4\n1 2\n-1 3\n-3 -2\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let mut graph = DirectedGraph::new();
+        formula.build_graph(&mut graph);
+        let solutions : Vec<Vec<bool>> = Solutions::new(&graph,4).collect();
+        // 2 solutions for 1, 2 and 3, and variable 4 is free
+        assert_eq!(solutions.len(),4);
+        assert!(solutions.iter().all(|assignment| satisfies(assignment,&formula.clauses)));
+        assert_eq!(count_solutions(&graph,4,100),SolutionCount::Exact(4));
+        // stops after the first solution of each group
+        assert_eq!(count_solutions(&graph,4,1),SolutionCount::AtLeast(1));
+        assert_eq!(Solutions::new(&graph,4).take(3).count(),3);
+
+        let formula = parse_formula("2\n1 1\n-1 -1\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let mut graph = CsrGraph::new();
+        formula.build_graph(&mut graph);
+        assert_eq!(Solutions::new(&graph,2).next(),None);
+        assert_eq!(count_solutions(&graph,2,100),SolutionCount::Exact(0));
+        // 2^100 assignments with no clauses at all
+        let mut graph = CsrGraph::new();
+        Formula::new(100).build_graph(&mut graph);
+        assert_eq!(count_solutions(&graph,100,10),SolutionCount::Exact(1 << 100));
+        // 2^200 doesn't fit
+        let mut graph = CsrGraph::new();
+        Formula::new(200).build_graph(&mut graph);
+        assert_eq!(count_solutions(&graph,200,10),SolutionCount::TooMany);
+    }
+
+    #[test]
+    fn matches_brute_force_count() {
+        for seed in 0..200 {
+            let num_variables = 1 + (seed % 10) as usize;
+            let formula = seeded_formula(seed,GeneratorMode::Random,num_variables,num_variables);
+            let mut graph = CsrGraph::new();
+            formula.build_graph(&mut graph);
+
+            let expected : HashSet<Vec<bool>> = brute_force_solutions(num_variables,&formula.clauses).into_iter().collect();
+            assert_eq!(count_solutions(&graph,num_variables,1 << 20),SolutionCount::Exact(expected.len() as u128),"seed {}",seed);
+            let solutions : HashSet<Vec<bool>> = Solutions::new(&graph,num_variables).collect();
+            assert_eq!(solutions,expected,"seed {}",seed);
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use log::info;
+use std::collections::HashMap;
+use std::io::{self,Write};
+use clap::ValueEnum;
+
+use crate::dirgraph::DirectedGraph;
+use crate::literal::Literal;
+use crate::scc::SccFinder;
+
+
+/// The formats a graph can be exported to
+#[derive(Debug,Clone,Copy,PartialEq,Eq,ValueEnum)]
+pub enum ExportFormat {
+    /// Graphviz
+    Dot,
+    Graphml,
+    /// node-link JSON (nodes and links lists, as read by networkx and d3)
+    Json,
+}
+
+impl ExportFormat {
+
+    /// Picks the format from a file name's extension, ignoring any compression extension
+    pub fn from_file_name(name: &str) -> Option<ExportFormat> {
+        let extension = name.to_lowercase().rsplit('.').find(|extension| !["gz","xz","zst"].contains(extension)).map(String::from);
+        match extension.as_deref() {
+            Some("dot") | Some("gv") => Some(ExportFormat::Dot),
+            Some("graphml") => Some(ExportFormat::Graphml),
+            Some("json") => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Fill colours for SCCs with more than one vertex;  single vertex SCCs aren't coloured
+const SCC_COLORS : [&str; 10] = ["lightblue","palegreen","khaki","plum","lightsalmon",
+                                 "paleturquoise","pink","wheat","lightgray","thistle"];
+const CONFLICT_COLOR : &str = "red";
+
+/// How one vertex is drawn
+struct NodeStyle {
+    id: isize,
+    scc: Option<isize>,
+    color: Option<&'static str>,
+    /// x and -x are both in the vertex's SCC
+    conflict: bool,
+}
+
+/// Writes a `DirectedGraph` (usually an implication graph) to DOT, GraphML or JSON, optionally
+/// colouring the vertexes by the SCCs found for it and highlighting the x / -x pairs that
+/// share an SCC
+pub struct GraphExport<'a> {
+    graph: &'a DirectedGraph,
+    k: Option<&'a dyn SccFinder>,
+    highlight_conflicts: bool,
+}
+
+impl<'a> GraphExport<'a> {
+
+    pub fn new(graph: &'a DirectedGraph) -> GraphExport<'a> {
+        GraphExport { graph, k: None, highlight_conflicts: false }
+    }
+
+    /// Colours the vertexes by SCC
+    pub fn scc(mut self, k: &'a dyn SccFinder) -> GraphExport<'a> {
+        self.k = Some(k);
+        self
+    }
+
+    /// Outlines the vertexes x and -x (and the edges between them) when they are in the same SCC.
+    /// Needs the SCCs
+    pub fn highlight_conflicts(mut self, highlight: bool) -> GraphExport<'a> {
+        self.highlight_conflicts = highlight;
+        self
+    }
+
+    fn node_styles(&self) -> Vec<NodeStyle> {
+        let mut sizes = HashMap::<isize,usize>::new();
+        if let Some(k) = self.k {
+            for group in self.graph.get_vertex_ids().iter().filter_map(|vertex| k.get_group(*vertex)) {
+                *sizes.entry(*group).or_insert(0) += 1;
+            }
+        }
+        let mut colors = HashMap::<isize,&'static str>::new();
+        self.graph.get_vertex_ids().into_iter().map(|id| {
+            let scc = self.k.and_then(|k| k.get_group(id).cloned());
+            let color = scc.filter(|group| sizes[group] > 1).map(|group| {
+                let next = SCC_COLORS[colors.len() % SCC_COLORS.len()];
+                *colors.entry(group).or_insert(next)
+            });
+            let conflict = self.highlight_conflicts && scc.is_some() && Literal::new(id)
+                .is_ok_and(|literal| self.k.and_then(|k| k.get_group(literal.negate().value()).cloned()) == scc);
+            NodeStyle { id, scc, color, conflict }
+        }).collect()
+    }
+
+    /// Whether an edge joins two vertexes that are both in the same conflicting SCC
+    fn conflict_edge(&self, styles: &HashMap<isize,&NodeStyle>, source: isize, dest: isize) -> bool {
+        match (styles.get(&source), styles.get(&dest)) {
+            (Some(from), Some(to)) => from.conflict && to.conflict && from.scc == to.scc,
+            _ => false,
+        }
+    }
+
+    pub fn write(&self, format: ExportFormat, out: &mut dyn Write) -> io::Result<()> {
+        let nodes = self.node_styles();
+        let styles : HashMap<isize,&NodeStyle> = nodes.iter().map(|node| (node.id, node)).collect();
+        // (source, dest, weight, part of a conflict)
+        let edges : Vec<(isize,isize,i32,bool)> = self.graph.edge_iter()
+            .map(|(_, edge)| (edge.source(), edge.dest(), edge.weight(), self.conflict_edge(&styles,edge.source(),edge.dest())))
+            .collect();
+        info!("Exporting graph with {} vertexes and {} edges as {:?}",nodes.len(),edges.len(),format);
+        match format {
+            ExportFormat::Dot => write_dot(&nodes,&edges,out),
+            ExportFormat::Graphml => write_graphml(&nodes,&edges,out),
+            ExportFormat::Json => write_json(&nodes,&edges,out),
+        }
+    }
+}
+
+
+fn write_dot(nodes: &[NodeStyle], edges: &[(isize,isize,i32,bool)], out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out,"digraph implications {{")?;
+    for node in nodes {
+        let mut attributes = Vec::<String>::new();
+        if let Some(color) = node.color {
+            attributes.push(format!("style=filled, fillcolor={}",color));
+        }
+        if node.conflict {
+            attributes.push(format!("color={}, penwidth=3",CONFLICT_COLOR));
+        }
+        if attributes.is_empty() {
+            writeln!(out,"  \"{}\";",node.id)?;
+        }
+        else {
+            writeln!(out,"  \"{}\" [{}];",node.id,attributes.join(", "))?;
+        }
+    }
+    for (source, dest, weight, conflict) in edges {
+        let mut attributes = Vec::<String>::new();
+        if *weight != 1 {
+            attributes.push(format!("label={}",weight));
+        }
+        if *conflict {
+            attributes.push(format!("color={}",CONFLICT_COLOR));
+        }
+        if attributes.is_empty() {
+            writeln!(out,"  \"{}\" -> \"{}\";",source,dest)?;
+        }
+        else {
+            writeln!(out,"  \"{}\" -> \"{}\" [{}];",source,dest,attributes.join(", "))?;
+        }
+    }
+    writeln!(out,"}}")
+}
+
+fn write_graphml(nodes: &[NodeStyle], edges: &[(isize,isize,i32,bool)], out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out,"<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(out,"<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">")?;
+    writeln!(out,"  <key id=\"scc\" for=\"node\" attr.name=\"scc\" attr.type=\"long\"/>")?;
+    writeln!(out,"  <key id=\"color\" for=\"node\" attr.name=\"color\" attr.type=\"string\"/>")?;
+    writeln!(out,"  <key id=\"conflict\" for=\"all\" attr.name=\"conflict\" attr.type=\"boolean\">")?;
+    writeln!(out,"    <default>false</default>")?;
+    writeln!(out,"  </key>")?;
+    writeln!(out,"  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"int\"/>")?;
+    writeln!(out,"  <graph id=\"implications\" edgedefault=\"directed\">")?;
+    for node in nodes {
+        writeln!(out,"    <node id=\"{}\">",node.id)?;
+        if let Some(scc) = node.scc {
+            writeln!(out,"      <data key=\"scc\">{}</data>",scc)?;
+        }
+        if let Some(color) = node.color {
+            writeln!(out,"      <data key=\"color\">{}</data>",color)?;
+        }
+        if node.conflict {
+            writeln!(out,"      <data key=\"conflict\">true</data>")?;
+        }
+        writeln!(out,"    </node>")?;
+    }
+    for (source, dest, weight, conflict) in edges {
+        writeln!(out,"    <edge source=\"{}\" target=\"{}\">",source,dest)?;
+        writeln!(out,"      <data key=\"weight\">{}</data>",weight)?;
+        if *conflict {
+            writeln!(out,"      <data key=\"conflict\">true</data>")?;
+        }
+        writeln!(out,"    </edge>")?;
+    }
+    writeln!(out,"  </graph>")?;
+    writeln!(out,"</graphml>")
+}
+
+fn write_json(nodes: &[NodeStyle], edges: &[(isize,isize,i32,bool)], out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out,"{{")?;
+    writeln!(out,"  \"directed\": true,")?;
+    writeln!(out,"  \"nodes\": [")?;
+    for (index, node) in nodes.iter().enumerate() {
+        let scc = node.scc.map_or("null".to_string(),|scc| scc.to_string());
+        let color = node.color.map_or("null".to_string(),|color| format!("\"{}\"",color));
+        let separator = if index + 1 < nodes.len() { "," } else { "" };
+        writeln!(out,"    {{\"id\": {}, \"scc\": {}, \"color\": {}, \"conflict\": {}}}{}",node.id,scc,color,node.conflict,separator)?;
+    }
+    writeln!(out,"  ],")?;
+    writeln!(out,"  \"links\": [")?;
+    for (index, (source, dest, weight, conflict)) in edges.iter().enumerate() {
+        let separator = if index + 1 < edges.len() { "," } else { "" };
+        writeln!(out,"    {{\"source\": {}, \"target\": {}, \"weight\": {}, \"conflict\": {}}}{}",source,dest,weight,conflict,separator)?;
+    }
+    writeln!(out,"  ]")?;
+    writeln!(out,"}}")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::dirgraph::DirectedGraph;
+    use crate::export::{ExportFormat,GraphExport};
+    use crate::kosaraju::Kosaraju;
+    use crate::parse::{parse_formula,InputFormat,ParseMode};
+    use crate::scc::SccFinder;
+
+    fn export(graph: &DirectedGraph, k: Option<&dyn SccFinder>, format: ExportFormat) -> String {
+        let mut export = GraphExport::new(graph).highlight_conflicts(true);
+        if let Some(k) = k {
+            export = export.scc(k);
+        }
+        let mut out = Vec::<u8>::new();
+        export.write(format,&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn export_formats() {
+        // 1 and -1 are in the same SCC;  2 and 3 aren't in any conflict
+        let formula = parse_formula("3\n1 1\n-1 -1\n2 3\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let mut graph = DirectedGraph::new();
+        formula.build_graph(&mut graph);
+        let mut k = Kosaraju::new(&graph,true);
+        k.find_scc();
+
+        let dot = export(&graph,Some(&k),ExportFormat::Dot);
+        assert!(dot.starts_with("digraph implications {\n"));
+        assert!(dot.contains("  \"-1\" [style=filled, fillcolor=lightblue, color=red, penwidth=3];\n"));
+        assert!(dot.contains("  \"-1\" -> \"1\" [color=red];\n"));
+        assert!(dot.contains("  \"-2\" -> \"3\";\n"));
+        assert!(!export(&graph,None,ExportFormat::Dot).contains("red"));
+
+        let graphml = export(&graph,Some(&k),ExportFormat::Graphml);
+        assert_eq!(graphml.matches("<node ").count(),6);
+        assert_eq!(graphml.matches("<edge ").count(),4);
+        assert_eq!(graphml.matches("<data key=\"conflict\">true</data>").count(),4);
+
+        let json = export(&graph,None,ExportFormat::Json);
+        assert!(json.contains("{\"id\": -3, \"scc\": null, \"color\": null, \"conflict\": false},\n"));
+        assert!(json.contains("{\"source\": -1, \"target\": 1, \"weight\": 1, \"conflict\": false}"));
+        assert!(json.trim_end().ends_with("]\n}"));
+
+        assert_eq!(ExportFormat::from_file_name("unsat.GV"),Some(ExportFormat::Dot));
+        assert_eq!(ExportFormat::from_file_name("graph.json.gz"),Some(ExportFormat::Json));
+        assert_eq!(ExportFormat::from_file_name("graph.txt"),None);
+    }
+}
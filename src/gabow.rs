@@ -0,0 +1,138 @@
+use log::{ info, debug, trace };
+use std::collections::HashMap;
+use crate::dirgraph::DirectedGraph;
+use crate::searchgraph::{SearchGraph,VertexMap};
+use crate::scc::{SccFinder,log_scc_groups};
+
+
+/// Gabow's path-based SCC algorithm.
+///
+/// Instead of the lowlink values used by Tarjan, this keeps a second stack with the
+/// boundaries of the SCCs along the current search path.  As with Tarjan the search
+/// uses an explicit stack rather than recursion.
+#[derive(Debug,Clone)]
+pub struct Gabow<'a, G: SearchGraph = DirectedGraph> {
+    graph:  &'a G,
+    /// order in which each vertex was first reached
+    preorder:  VertexMap<'a, G, usize>,
+    /// vertexes that have been reached but not yet assigned to an SCC
+    unassigned:  Vec<isize>,
+    /// vertexes on the search path that may be the root of an SCC
+    boundaries:  Vec<isize>,
+    vertex_scc_map: VertexMap<'a, G, isize>,
+    scc_members:  HashMap<isize,Vec<isize>>,
+    scc_rank:  VertexMap<'a, G, usize>,
+}
+
+
+impl<'a, G: SearchGraph> Gabow<'a, G> {
+
+    pub fn new(graph: &'a G) -> Gabow<'a, G> {
+        Gabow {
+            graph,
+            preorder:  VertexMap::new(graph),
+            unassigned:  Vec::<isize>::new(),
+            boundaries:  Vec::<isize>::new(),
+            vertex_scc_map:  VertexMap::new(graph),
+            scc_members:  HashMap::<isize,Vec<isize>>::new(),
+            scc_rank:  VertexMap::new(graph),
+        }
+    }
+
+    fn visit(&mut self, vertex_id: isize) {
+        let order = self.preorder.len();
+        trace!("Gabow visiting {} preorder {}",vertex_id,order);
+        self.preorder.insert(vertex_id,order);
+        self.unassigned.push(vertex_id);
+        self.boundaries.push(vertex_id);
+    }
+
+    /// Pops the SCC rooted at vertex_id off the stack of unassigned vertexes
+    fn pop_scc(&mut self, vertex_id: isize) {
+        self.boundaries.pop();
+        let mut members = Vec::<isize>::new();
+        loop {
+            let member = self.unassigned.pop().unwrap();
+            self.vertex_scc_map.insert(member,vertex_id);
+            members.push(member);
+            if member == vertex_id {
+                break;
+            }
+        }
+        debug!("Gabow SCC {} has {} vertexes",vertex_id,members.len());
+        let rank = self.scc_rank.len();
+        self.scc_rank.insert(vertex_id,rank);
+        self.scc_members.insert(vertex_id,members);
+    }
+
+    /// Performs the depth first search starting at a vertex that hasn't been reached yet
+    fn search(&mut self, start_vertex: isize) {
+
+        // each entry is a vertex being searched, its outgoing vertexes and the next one to check
+        let mut call_stack = Vec::<(isize, Vec<isize>, usize)>::new();
+        self.visit(start_vertex);
+        call_stack.push((start_vertex, self.graph.get_outgoing_vertex_ids(start_vertex), 0));
+
+        while let Some((vertex_id, outgoing, next)) = call_stack.last_mut() {
+            let vertex_id = *vertex_id;
+            if *next < outgoing.len() {
+                let dest = outgoing[*next];
+                *next += 1;
+                match self.preorder.get(dest) {
+                    None => {
+                        self.visit(dest);
+                        call_stack.push((dest, self.graph.get_outgoing_vertex_ids(dest), 0));
+                    },
+                    Some(dest_order) if !self.vertex_scc_map.contains_key(dest) => {
+                        // found a cycle back to dest, so everything on the path after dest
+                        // is part of the same SCC
+                        let dest_order = *dest_order;
+                        while self.preorder.get(*self.boundaries.last().unwrap()).is_some_and(|order| *order > dest_order) {
+                            self.boundaries.pop();
+                        }
+                    },
+                    Some(_) => {},
+                }
+            }
+            else {
+                call_stack.pop();
+                if self.boundaries.last() == Some(&vertex_id) {
+                    self.pop_scc(vertex_id);
+                }
+            }
+        }
+    }
+}
+
+
+impl<'a, G: SearchGraph> SccFinder for Gabow<'a, G> {
+
+    fn find_scc(&mut self) {
+        for vertex_id in self.graph.get_vertex_ids() {
+            if !self.preorder.contains_key(vertex_id) {
+                self.search(vertex_id);
+            }
+        }
+        info!("Gabow found {} SCCs",self.scc_members.len());
+    }
+
+    fn get_group(&self, vertex_id: isize) -> Option<&isize> {
+        self.vertex_scc_map.get(vertex_id)
+    }
+
+    fn get_scc_rank(&self, vertex_id: isize) -> Option<usize> {
+        self.get_group(vertex_id).and_then(|group| self.scc_rank.get(*group).cloned())
+    }
+
+    fn get_scc_sizes(&self) -> Vec<usize> {
+        self.scc_members.values().map(|members| members.len()).collect()
+    }
+
+    fn get_scc_group(&self, group: isize) -> Vec<isize> {
+        self.scc_members.get(&group).cloned().unwrap_or_default()
+    }
+
+    fn log_scc_to_files(&self, prefix: &str) {
+        log_scc_groups(&self.scc_members,prefix);
+    }
+}
@@ -0,0 +1,148 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng,SeedableRng};
+use clap::ValueEnum;
+use log::{ info, debug };
+
+use crate::literal::{Literal,Variable};
+use crate::parse::Clause;
+
+
+/// The kinds of instances that can be generated
+#[derive(Debug,Clone,Copy,PartialEq,Eq,ValueEnum)]
+pub enum GeneratorMode {
+    /// uniformly random clauses; around the threshold (clauses = variables) about half are satisfiable
+    Random,
+    /// every clause is satisfied by a hidden random assignment, so the instance is satisfiable
+    Planted,
+    /// contains the implication cycle x -> ... -> -x -> ... -> x, so the instance is unsatisfiable
+    Unsat,
+}
+
+
+/// Random 2-SAT instance generator.  The same seed always produces the same instance
+pub struct Generator {
+    rng: StdRng,
+    num_variables: usize,
+}
+
+impl Generator {
+
+    pub fn new(num_variables: usize, seed: u64) -> Generator {
+        Generator { rng: StdRng::seed_from_u64(seed), num_variables }
+    }
+
+    fn random_variable(&mut self) -> Variable {
+        Variable::from_index(self.rng.gen_range(0..self.num_variables))
+    }
+
+    fn random_literal(&mut self) -> Literal {
+        let var = self.random_variable();
+        var.literal(self.rng.gen_bool(0.5))
+    }
+
+    /// Two random literals, using different variables when there is more than one variable
+    fn random_pair(&mut self) -> (Literal,Literal) {
+        let first = self.random_literal();
+        let mut second = self.random_literal();
+        while self.num_variables > 1 && second.var() == first.var() {
+            second = self.random_literal();
+        }
+        (first,second)
+    }
+
+    fn random_clauses(&mut self, num_clauses: usize) -> Vec<(Literal,Literal)> {
+        (0..num_clauses).map(|_| self.random_pair()).collect()
+    }
+
+    /// Random clauses that are each satisfied by a hidden assignment
+    fn planted_clauses(&mut self, num_clauses: usize) -> Vec<(Literal,Literal)> {
+        let assignment : Vec<bool> = (0..self.num_variables).map(|_| self.rng.gen_bool(0.5)).collect();
+        debug!("Planted assignment {:?}",assignment);
+        let is_true = |literal: Literal| literal.is_true(&assignment) == Some(true);
+        (0..num_clauses).map(|_| {
+            let (first, second) = self.random_pair();
+            if is_true(first) || is_true(second) {
+                (first,second)
+            }
+            // flip one of the literals so the clause is satisfied
+            else if self.rng.gen_bool(0.5) {
+                (-first,second)
+            }
+            else {
+                (first,-second)
+            }
+        }).collect()
+    }
+
+    /// The clauses for a chain of implications from one literal to another through random literals
+    fn implication_chain(&mut self, from: Literal, to: Literal, length: usize) -> Vec<(Literal,Literal)> {
+        let mut clauses = Vec::<(Literal,Literal)>::new();
+        let mut current = from;
+        for _ in 1..length {
+            let next = self.random_literal();
+            // current -> next is the clause (-current OR next)
+            clauses.push((-current,next));
+            current = next;
+        }
+        clauses.push((-current,to));
+        clauses
+    }
+
+    /// Random clauses along with a contradiction cycle x -> -x -> x through cycle_length
+    /// implications in each direction
+    fn unsat_clauses(&mut self, num_clauses: usize, cycle_length: usize) -> Result<Vec<(Literal,Literal)>,String> {
+        let cycle_length = cycle_length.max(1);
+        if num_clauses < 2 * cycle_length {
+            return Err(format!("an unsatisfiable instance with a cycle length of {} needs at least {} clauses",cycle_length,2*cycle_length));
+        }
+        let var = self.random_variable();
+        let mut clauses = self.implication_chain(var.positive(),var.negative(),cycle_length);
+        clauses.extend(self.implication_chain(var.negative(),var.positive(),cycle_length));
+        debug!("Contradiction cycle for variable {}: {:?}",var,clauses);
+        clauses.extend(self.random_clauses(num_clauses - clauses.len()));
+        Ok(clauses)
+    }
+
+    /// Generates the clauses for an instance, numbered by the line they'd be on in the course format
+    pub fn generate(&mut self, mode: GeneratorMode, num_clauses: usize, cycle_length: usize) -> Result<Vec<Clause>,String> {
+
+        if self.num_variables == 0 {
+            return Err("the number of variables must be at least 1".to_string());
+        }
+        let mut pairs = match mode {
+            GeneratorMode::Random => self.random_clauses(num_clauses),
+            GeneratorMode::Planted => self.planted_clauses(num_clauses),
+            GeneratorMode::Unsat => self.unsat_clauses(num_clauses,cycle_length)?,
+        };
+        pairs.shuffle(&mut self.rng);
+        info!("Generated {} {:?} clauses for {} variables",pairs.len(),mode,self.num_variables);
+        Ok(pairs.into_iter().enumerate().map(|(index, (first, second))| Clause { first, second, line: index+2, label: None }).collect())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::{Generator,GeneratorMode};
+    use crate::unsatcore::clauses_satisfiable;
+
+    #[test]
+    fn generate_instances() {
+        for seed in 0..20 {
+            let planted = Generator::new(50,seed).generate(GeneratorMode::Planted,200,5).unwrap();
+            assert_eq!(planted.len(),200);
+            assert!(clauses_satisfiable(&planted));
+            let unsat = Generator::new(50,seed).generate(GeneratorMode::Unsat,60,5).unwrap();
+            assert!(!clauses_satisfiable(&unsat));
+            assert!(unsat.iter().all(|c| c.first.var().number() <= 50 && c.second.var().number() <= 50));
+        }
+        let unsat = Generator::new(1,7).generate(GeneratorMode::Unsat,2,1).unwrap();
+        assert!(!clauses_satisfiable(&unsat));
+
+        let first = Generator::new(10,42).generate(GeneratorMode::Random,10,5).unwrap();
+        assert_eq!(first,Generator::new(10,42).generate(GeneratorMode::Random,10,5).unwrap());
+        assert!(Generator::new(10,1).generate(GeneratorMode::Unsat,5,5).is_err());
+        assert!(Generator::new(0,1).generate(GeneratorMode::Random,5,5).is_err());
+    }
+}
@@ -1,5 +1,22 @@
+use crate::literal::{Literal,Variable};
+
+
 pub trait GraphBuilder {
     fn add_edge(&mut self, source: isize,dest: isize,weight: i32) -> Option<usize>;
-    fn add_vertex(&mut self, id:  isize); 
-}
+    fn add_vertex(&mut self, id:  isize);
+    /// called once all of the vertexes and edges have been added
+    fn finish(&mut self) {}
+    /// hint of how many vertexes and edges are about to be added
+    fn reserve(&mut self, _num_vertexes: usize, _num_edges: usize) {}
 
+    /// adds the vertexes for a variable and its negation
+    fn add_variable(&mut self, var: Variable) {
+        self.add_vertex(var.positive().value());
+        self.add_vertex(var.negative().value());
+    }
+
+    /// adds the edge for the implication from -> to, returning its edge id
+    fn add_implication(&mut self, from: Literal, to: Literal) -> Option<usize> {
+        self.add_edge(from.value(), to.value(), 1)
+    }
+}
@@ -0,0 +1,284 @@
+use log::{ info, debug };
+use std::collections::{BTreeMap,BTreeSet,HashMap};
+
+use crate::dirgraph::DirectedGraph;
+use crate::graphbuilder::GraphBuilder;
+use crate::conflict::find_path;
+use crate::literal::{Literal,Variable};
+use crate::parse::Formula;
+use crate::scc::SccFinder;
+use crate::solver::find_assignment;
+use crate::tarjan::Tarjan;
+
+
+/// A clause added to the incremental solver along with the two implication edges it created
+#[derive(Debug,Clone)]
+struct SolverClause {
+    first: Literal,
+    second: Literal,
+    edges: (usize,usize),
+}
+
+/// What is known about the current set of clauses
+#[derive(Debug,Clone)]
+enum Status {
+    /// clauses have changed in a way that requires the SCCs to be found again
+    Unknown,
+    Satisfiable(Vec<bool>),
+    /// can't be satisfied, along with the ids of the clauses in a conflicting cycle
+    Unsatisfiable(BTreeSet<usize>),
+}
+
+
+/// 2-SAT solver that owns its implication graph and allows clauses to be added
+/// and removed between checks.
+///
+/// The result of the last check is reused when possible; adding a clause that the current
+/// assignment already satisfies keeps the formula satisfiable, adding a clause to an
+/// unsatisfiable formula keeps it unsatisfiable, and removing a clause that isn't part of
+/// the conflicting cycle keeps it unsatisfiable.  Scopes can be pushed and popped to try
+/// out clauses and then roll them back.
+#[derive(Debug,Clone)]
+pub struct IncrementalSolver {
+    graph: DirectedGraph,
+    num_variables: usize,
+    clauses: BTreeMap<usize,SolverClause>,
+    /// maps an edge id in the graph to the id of the clause that created it
+    edge_clause: HashMap<usize,usize>,
+    next_clause_id: usize,
+    /// ids of the clauses added in each of the open scopes
+    scopes: Vec<Vec<usize>>,
+    status: Status,
+}
+
+
+impl IncrementalSolver {
+
+    pub fn new(num_variables: usize) -> IncrementalSolver {
+        let mut solver = IncrementalSolver {
+            graph: DirectedGraph::new(),
+            num_variables: 0,
+            clauses: BTreeMap::<usize,SolverClause>::new(),
+            edge_clause: HashMap::<usize,usize>::new(),
+            next_clause_id: 1,
+            scopes: Vec::<Vec<usize>>::new(),
+            status: Status::Unknown,
+        };
+        solver.add_variables(num_variables);
+        solver
+    }
+
+    /// Creates a solver with all of the clauses from a formula.  The clause ids are
+    /// assigned in the order of the formula's clauses starting at 1
+    pub fn from_formula(formula: &Formula) -> IncrementalSolver {
+        let mut solver = IncrementalSolver::new(formula.num_variables);
+        for clause in &formula.clauses {
+            solver.add_clause(clause.first,clause.second);
+        }
+        solver
+    }
+
+    /// Makes sure the graph has the vertexes for variables 1..=num_variables
+    fn add_variables(&mut self, num_variables: usize) {
+        let mut g = &mut self.graph;
+        for index in self.num_variables..num_variables {
+            g.add_variable(Variable::from_index(index));
+        }
+        if num_variables > self.num_variables {
+            self.num_variables = num_variables;
+        }
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.num_variables
+    }
+
+    pub fn clause_count(&self) -> usize {
+        self.clauses.len()
+    }
+
+    /// Adds the clause (first OR second) and returns its id
+    pub fn add_clause(&mut self, first: Literal, second: Literal) -> usize {
+
+        self.add_variables(first.var().number().max(second.var().number()));
+
+        let clause_id = self.next_clause_id;
+        self.next_clause_id += 1;
+        let mut g = &mut self.graph;
+        let first_edge = g.add_implication(first.negate(),second).unwrap();
+        let second_edge = g.add_implication(second.negate(),first).unwrap();
+        self.edge_clause.insert(first_edge,clause_id);
+        self.edge_clause.insert(second_edge,clause_id);
+        self.clauses.insert(clause_id,SolverClause { first, second, edges: (first_edge,second_edge) });
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(clause_id);
+        }
+        debug!("Added clause {} ({} {})",clause_id,first,second);
+
+        if let Status::Satisfiable(assignment) = &mut self.status {
+            // any new variables are only used by this clause so they can start out false
+            assignment.resize(self.num_variables,false);
+            if first.is_true(assignment) != Some(true) && second.is_true(assignment) != Some(true) {
+                self.status = Status::Unknown;
+            }
+        }
+        clause_id
+    }
+
+    /// Removes a previously added clause
+    pub fn remove_clause(&mut self, clause_id: usize) -> Result<(),String> {
+
+        let clause = match self.clauses.remove(&clause_id) {
+            Some(clause) => clause,
+            None => return Err(format!("Remove Clause: No such clause {}",clause_id)),
+        };
+        self.graph.delete_edge(clause.edges.0)?;
+        self.graph.delete_edge(clause.edges.1)?;
+        self.edge_clause.remove(&clause.edges.0);
+        self.edge_clause.remove(&clause.edges.1);
+        for scope in self.scopes.iter_mut() {
+            scope.retain(|id| *id != clause_id);
+        }
+        debug!("Removed clause {} ({} {})",clause_id,clause.first,clause.second);
+
+        if let Status::Unsatisfiable(conflict) = &self.status {
+            if conflict.contains(&clause_id) {
+                self.status = Status::Unknown;
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens a new scope;  clauses added until the matching pop() are removed by it
+    pub fn push(&mut self) {
+        self.scopes.push(Vec::<usize>::new());
+    }
+
+    /// Removes all of the clauses added since the last push()
+    pub fn pop(&mut self) -> Result<(),String> {
+        let scope = match self.scopes.pop() {
+            Some(scope) => scope,
+            None => return Err("Pop: no open scope".to_string()),
+        };
+        for clause_id in scope.iter().rev() {
+            self.remove_clause(*clause_id)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of open scopes
+    pub fn scope_depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Finds the SCCs if the last result can't be reused
+    fn solve(&mut self) {
+
+        if !matches!(self.status, Status::Unknown) {
+            debug!("Reusing previous result");
+            return;
+        }
+
+        let mut k = Tarjan::new(&self.graph);
+        k.find_scc();
+        self.status = match find_assignment(&k,self.num_variables) {
+            Some(assignment) => Status::Satisfiable(assignment),
+            None => Status::Unsatisfiable(self.conflict_clauses(&k)),
+        };
+        info!("Solved {} clauses: {}",self.clauses.len(),matches!(self.status, Status::Satisfiable(_)));
+    }
+
+    /// Finds the ids of the clauses on a conflicting cycle x -> -x -> x
+    fn conflict_clauses(&self, k: &dyn SccFinder) -> BTreeSet<usize> {
+
+        let mut conflict = BTreeSet::<usize>::new();
+        for index in 0..self.num_variables {
+            let var = Variable::from_index(index);
+            let (positive, negative) = (var.positive().value(), var.negative().value());
+            let group = match k.get_group(positive) {
+                Some(group) if k.get_group(negative) == Some(group) => *group,
+                _ => continue,
+            };
+            let in_scc = |v: isize| k.get_group(v) == Some(&group);
+            let forward = find_path(&self.graph,positive,negative,in_scc).unwrap_or_default();
+            let backward = find_path(&self.graph,negative,positive,in_scc).unwrap_or_default();
+            conflict.extend(forward.iter().chain(backward.iter()).filter_map(|edge_id| self.edge_clause.get(edge_id)));
+            break;
+        }
+        conflict
+    }
+
+    pub fn is_satisfiable(&mut self) -> bool {
+        self.solve();
+        matches!(self.status, Status::Satisfiable(_))
+    }
+
+    /// Returns a satisfying assignment (entry i is variable i+1) or None
+    pub fn assignment(&mut self) -> Option<Vec<bool>> {
+        self.solve();
+        match &self.status {
+            Status::Satisfiable(assignment) => Some(assignment.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn graph(&self) -> &DirectedGraph {
+        &self.graph
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::bruteforce::lit;
+    use crate::incremental::IncrementalSolver;
+
+    #[test]
+    fn add_and_remove() {
+        let mut solver = IncrementalSolver::new(2);
+        let c1 = solver.add_clause(lit(1),lit(2));
+        let c2 = solver.add_clause(lit(-1),lit(2));
+        assert!(solver.is_satisfiable());
+        assert!(solver.assignment().unwrap()[1]);
+        let c3 = solver.add_clause(lit(1),lit(-2));
+        assert!(solver.is_satisfiable());
+        let c4 = solver.add_clause(lit(-1),lit(-2));
+        assert!(!solver.is_satisfiable());
+        assert_eq!(solver.assignment(),None);
+        solver.remove_clause(c3).unwrap();
+        assert!(solver.is_satisfiable());
+        assert_eq!(solver.assignment(),Some(vec!(false,true)));
+        assert!(solver.remove_clause(c3).is_err());
+        solver.remove_clause(c1).unwrap();
+        solver.remove_clause(c2).unwrap();
+        solver.remove_clause(c4).unwrap();
+        assert_eq!(solver.clause_count(),0);
+        assert!(solver.is_satisfiable());
+    }
+
+    #[test]
+    fn push_and_pop() {
+        let mut solver = IncrementalSolver::new(3);
+        solver.add_clause(lit(1),lit(2));
+        solver.add_clause(lit(-1),lit(3));
+        assert!(solver.is_satisfiable());
+
+        solver.push();
+        solver.add_clause(lit(-2),lit(-2));
+        solver.add_clause(lit(-3),lit(-3));
+        assert!(!solver.is_satisfiable());
+        solver.push();
+        // new variable beyond the original count
+        solver.add_clause(lit(4),lit(4));
+        assert_eq!(solver.num_variables(),4);
+        assert!(!solver.is_satisfiable());
+        solver.pop().unwrap();
+        assert!(!solver.is_satisfiable());
+        solver.pop().unwrap();
+        assert!(solver.is_satisfiable());
+        assert_eq!(solver.clause_count(),2);
+        solver.add_clause(lit(5),lit(1));
+        assert_eq!(solver.assignment().unwrap().len(),5);
+        assert!(solver.pop().is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use std::fs::File;
+use std::io::{self,BufRead,BufReader,BufWriter,Cursor,Read,Write};
+use std::path::Path;
+use log::debug;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use xz2::read::XzDecoder;
+use xz2::write::XzEncoder;
+
+
+/// An input stream that can be handed to the parser (and to a worker thread)
+pub type InputReader = Box<dyn BufRead + Send>;
+
+/// The compression formats that are decompressed while reading
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Works out the compression from the first bytes of the input
+pub fn compression_from_magic(prefix: &[u8]) -> Compression {
+    if prefix.starts_with(GZIP_MAGIC) {
+        Compression::Gzip
+    }
+    else if prefix.starts_with(XZ_MAGIC) {
+        Compression::Xz
+    }
+    else if prefix.starts_with(ZSTD_MAGIC) {
+        Compression::Zstd
+    }
+    else {
+        Compression::None
+    }
+}
+
+/// Works out the compression from the file extension
+pub fn compression_from_name(name: &str) -> Compression {
+    match Path::new(name).extension().and_then(|ext| ext.to_str()) {
+        Some("gz") => Compression::Gzip,
+        Some("xz") => Compression::Xz,
+        Some("zst") => Compression::Zstd,
+        _ => Compression::None,
+    }
+}
+
+/// Wraps a reader so that compressed data is decompressed.  The compression is found from
+/// the magic bytes at the start of the data, falling back on the extension of the name
+pub fn decompress<R: Read + Send + 'static>(mut reader: R, name: &str) -> io::Result<InputReader> {
+
+    // read just enough to check the magic bytes, then put them back in front of the rest
+    let mut prefix = Vec::<u8>::new();
+    (&mut reader).take(XZ_MAGIC.len() as u64).read_to_end(&mut prefix)?;
+    let compression = match compression_from_magic(&prefix) {
+        Compression::None => compression_from_name(name),
+        compression => compression,
+    };
+    debug!("Reading {} with compression {:?}",name,compression);
+
+    let reader = Cursor::new(prefix).chain(reader);
+    let input : InputReader = match compression {
+        Compression::None => Box::new(BufReader::new(reader)),
+        Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        Compression::Xz => Box::new(BufReader::new(XzDecoder::new_multi_decoder(reader))),
+        Compression::Zstd => Box::new(BufReader::new(zstd::stream::read::Decoder::new(reader)?)),
+    };
+    Ok(input)
+}
+
+/// Opens the named file (or stdin for "-") for reading, decompressing it if needed
+pub fn open_input(name: &str) -> io::Result<InputReader> {
+    if name == "-" {
+        decompress(io::stdin(),name)
+    }
+    else {
+        decompress(File::open(name)?,name)
+    }
+}
+
+/// A file (or stdout) being written, possibly through a compressor
+pub enum OutputWriter {
+    Stdout(BufWriter<io::Stdout>),
+    File(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Xz(XzEncoder<BufWriter<File>>),
+    Zstd(zstd::stream::write::Encoder<'static,BufWriter<File>>),
+}
+
+impl OutputWriter {
+
+    /// Writes the compression trailer (if any) and flushes the output.  The output isn't
+    /// complete until this has been called, and dropping the writer instead ignores any error
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            OutputWriter::Stdout(mut out) => out.flush(),
+            OutputWriter::File(mut out) => out.flush(),
+            OutputWriter::Gzip(encoder) => encoder.finish()?.flush(),
+            OutputWriter::Xz(encoder) => encoder.finish()?.flush(),
+            OutputWriter::Zstd(encoder) => encoder.finish()?.flush(),
+        }
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            OutputWriter::Stdout(out) => out,
+            OutputWriter::File(out) => out,
+            OutputWriter::Gzip(encoder) => encoder,
+            OutputWriter::Xz(encoder) => encoder,
+            OutputWriter::Zstd(encoder) => encoder,
+        }
+    }
+}
+
+impl Write for OutputWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer().flush()
+    }
+}
+
+/// Creates the named file (or stdout for "-") for writing, compressing it based on the extension.
+/// The data is only complete once `finish` has been called on the returned writer
+pub fn create_output(name: &str) -> io::Result<OutputWriter> {
+    if name == "-" {
+        return Ok(OutputWriter::Stdout(BufWriter::new(io::stdout())));
+    }
+    let file = BufWriter::new(File::create(name)?);
+    let output = match compression_from_name(name) {
+        Compression::None => OutputWriter::File(file),
+        Compression::Gzip => OutputWriter::Gzip(GzEncoder::new(file,flate2::Compression::default())),
+        Compression::Xz => OutputWriter::Xz(XzEncoder::new(file,6)),
+        Compression::Zstd => OutputWriter::Zstd(zstd::stream::write::Encoder::new(file,0)?),
+    };
+    Ok(output)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read,Write};
+    use flate2::write::GzEncoder;
+    use crate::input::{decompress,create_output,compression_from_magic,compression_from_name,Compression,open_input};
+
+    fn read_all(data: Vec<u8>, name: &str) -> String {
+        let mut text = String::new();
+        decompress(std::io::Cursor::new(data),name).unwrap().read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn compression_detection() {
+        assert_eq!(compression_from_name("formula.txt.gz"),Compression::Gzip);
+        assert_eq!(compression_from_name("formula.cnf.zst"),Compression::Zstd);
+        assert_eq!(compression_from_name("-"),Compression::None);
+        assert_eq!(compression_from_magic(&[0xfd,b'7',b'z',b'X',b'Z',0x00,1]),Compression::Xz);
+        assert_eq!(compression_from_magic(b"2\n"),Compression::None);
+
+        let text = "3\n1 2\n-1 3\n";
+        let mut encoder = GzEncoder::new(Vec::new(),flate2::Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        // found from the magic bytes even without the extension
+        assert_eq!(read_all(encoder.finish().unwrap(),"formula.txt"),text);
+        assert_eq!(read_all(zstd::encode_all(text.as_bytes(),0).unwrap(),"-"),text);
+        // short uncompressed input
+        assert_eq!(read_all(b"1\n".to_vec(),"formula.txt"),"1\n");
+    }
+
+    #[test]
+    fn compressed_output() {
+        let text = "3\n1 2\n-1 3\n";
+        for extension in ["gz","xz","zst"] {
+            let path = std::env::temp_dir().join(format!("sat2_output_{}.txt.{}",std::process::id(),extension));
+            let name = path.to_str().unwrap();
+            let mut out = create_output(name).unwrap();
+            out.write_all(text.as_bytes()).unwrap();
+            out.finish().unwrap();
+            let mut read = String::new();
+            open_input(name).unwrap().read_to_string(&mut read).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(read,text);
+        }
+    }
+}
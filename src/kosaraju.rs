@@ -1,54 +1,90 @@
-use log::{ info, debug, trace };
+use log::{ info, debug, trace, error };
 use std::collections::{HashMap};
 use crate::dirgraph::DirectedGraph;
+use crate::searchgraph::{SearchGraph,VertexMap};
+use crate::scc::{SccFinder,log_scc_groups};
 use std::io::{stdout,Write};
 
 use crate::log_files::LogFile;
 
 #[derive(Debug,Clone)]
-pub struct Kosaraju<'a> {
-    graph:  &'a DirectedGraph,
-    explored:  HashMap<isize,bool>,
-    finished:  HashMap<isize,bool>,
+pub struct Kosaraju<'a, G: SearchGraph = DirectedGraph> {
+    graph:  &'a G,
+    explored:  VertexMap<'a, G, ()>,
+    finished:  VertexMap<'a, G, ()>,
 	finished_order:  Vec::<isize>,
 	start_search:  HashMap::<isize,Vec::<isize>>,
-    vertex_scc_map: HashMap::<isize,isize>,
+    vertex_scc_map: VertexMap<'a, G, isize>,
 	top_search_cnts:  HashMap::<isize, usize>,
+    /// rank of each SCC (by its starting vertex) in the order it was found by the outgoing search
+    scc_rank:  VertexMap<'a, G, usize>,
     use_iter: bool,
+    /// write the vertexes in the order each search explores them to the log directory
+    log_explored: bool,
+}
+
+/// Writes an explored vertex to the log file, if there is one
+fn write_explored(logfile: Option<&LogFile>, vertex_id: isize) {
+    if let Some(logfile) = logfile {
+        let _ = writeln!(logfile.file(),"{}",vertex_id);
+    }
+}
+
+/// Creates a log file for the explored vertexes, carrying on without one if it can't be created
+fn explored_log(enabled: bool, name: &str) -> Option<LogFile> {
+    if !enabled {
+        return None;
+    }
+    match LogFile::new(name) {
+        Ok(logfile) => Some(logfile),
+        Err(why) => {
+            error!("Not logging the explored vertexes: {}",why);
+            None
+        }
+    }
 }
 
 
-impl<'a> Kosaraju<'a> {
+impl<'a, G: SearchGraph> Kosaraju<'a, G> {
 
-	pub fn new(graph: &DirectedGraph,use_iter : bool) -> Kosaraju {
+	pub fn new(graph: &'a G,use_iter : bool) -> Kosaraju<'a, G> {
         debug!("Use iter {}",use_iter);
 		Kosaraju {
             graph,
-            explored:  HashMap::<isize,bool>::new(),
-            finished:  HashMap::<isize,bool>::new(),
+            explored:  VertexMap::new(graph),
+            finished:  VertexMap::new(graph),
             finished_order:  Vec::<isize>::new(),
             start_search : HashMap::<isize,Vec::<isize>>::new(),
-            vertex_scc_map: HashMap::<isize,isize>::new(),
+            vertex_scc_map: VertexMap::new(graph),
             top_search_cnts : HashMap::<isize,usize>::new(),
+            scc_rank : VertexMap::new(graph),
             use_iter,
+            log_explored: false,
 		}
 	}
 
+    /// Logs the vertexes in the order they're explored to the "kosara_explored_in" and
+    /// "kosara_explored_out" files in the log directory (off by default)
+    pub fn log_explored(mut self, enabled: bool) -> Kosaraju<'a, G> {
+        self.log_explored = enabled;
+        self
+    }
+
     pub fn is_explored(&self, vertex_id: isize) -> bool {
-        self.explored.contains_key(&vertex_id) 
+        self.explored.contains_key(vertex_id)
     }
 
     pub fn mark_explored(&mut self, vertex_id: &isize) {
-        self.explored.insert(*vertex_id,true); 
+        self.explored.insert(*vertex_id,());
     }
 
     pub fn is_finished(&self, vertex_id: isize) -> bool {
-        self.finished.contains_key(&vertex_id) 
+        self.finished.contains_key(vertex_id)
     }
 
     pub fn mark_finished(&mut self, vertex_id: &isize)  {
-        self.finished.insert(*vertex_id,true); 
-        trace!("Adding {} - finsihed now {:?}",vertex_id,self.finished);
+        self.finished.insert(*vertex_id,());
+        trace!("Adding {} - finished now {} vertexes",vertex_id,self.finished.len());
     }
 
     pub fn set_group(&mut self, vertex_id: isize, group: isize)  {
@@ -56,10 +92,7 @@ impl<'a> Kosaraju<'a> {
         self.vertex_scc_map.insert(vertex_id,group); 
     }
 
-    pub fn get_group(&self, vertex_id: isize) -> Option<&isize> {
 
-        self.vertex_scc_map.get(&vertex_id) 
-    }
 
 
 
@@ -68,28 +101,39 @@ impl<'a> Kosaraju<'a> {
 			self.top_search_cnts.insert(vertex,count);
 	}
 
-    /// Perform the next level of the Depth First Search on the outgoing edges
-    /// from a specfic vertex
-	pub fn dfs_outgoing(&mut self, vertex_id:  isize, start_vertex: isize, logfile: &LogFile) {
-			
-        debug!("Outgoing Exploring {} - start vertex is {}",vertex_id, start_vertex);
-        // Set current node to explored
-        self.explored.insert(vertex_id,true);
-        log_writeln!(logfile,"{}",vertex_id);
-    
-        // Add this vertex to the group associcated with the 
-        // starting vertex, and update the list of top counts (TODO: is this needed)?
+    /// Add this vertex to the group associcated with the starting vertex,
+    /// and update the list of top counts
+    fn add_to_group(&mut self, vertex_id: isize, start_vertex: isize) {
         let start_group = self.start_search.entry(start_vertex)
                             .or_insert(Vec::<isize>::new());
         start_group.push(vertex_id);
-        let new_len = start_group.len().clone();
+        let new_len = start_group.len();
         self.add_search_entry(start_vertex,new_len);
         self.set_group(vertex_id,start_vertex);
         trace!("Added vertex {} to list {} len now {}",vertex_id,start_vertex,new_len);
+    }
+
+    pub fn dfs_outgoing(&mut self, vertex_id:  isize, start_vertex: isize, logfile: Option<&LogFile>) {
+
+        if self.use_iter {
+            self.dfs_out_iter(vertex_id,start_vertex,logfile);
+        }
+        else {
+            self.dfs_out(vertex_id,start_vertex,logfile);
+        }
+    }
 
+    /// Perform the next level of the Depth First Search on the outgoing edges
+    /// from a specfic vertex
+	pub fn dfs_out(&mut self, vertex_id:  isize, start_vertex: isize, logfile: Option<&LogFile>) {
+			
+        debug!("Outgoing Exploring {} - start vertex is {}",vertex_id, start_vertex);
+        // Set current node to explored
+        self.explored.insert(vertex_id,());
+        write_explored(logfile,vertex_id);
+        self.add_to_group(vertex_id,start_vertex);
 
-        for edge in self.graph.get_outgoing_edges(vertex_id) {
-            let dest_vertex = edge.dest();
+        for dest_vertex in self.graph.get_outgoing_vertex_ids(vertex_id) {
             trace!("Checking outgoing to {}",dest_vertex);
            // trace!("Processing {} edge {:?} with dest {}",vertex_id,edge,dest_vertex);
             if !self.is_explored(dest_vertex) {
@@ -107,7 +151,37 @@ impl<'a> Kosaraju<'a> {
 			
 	}
 
-    pub fn dfs_incoming(&mut self, vertex_id:  isize, logfile: &LogFile) {
+    /// Perform the Depth First Search on the outgoing edges from a specfic vertex
+    /// using an explicit stack, adding every vertex reached to the start vertex's group
+	pub fn dfs_out_iter(&mut self, vertex_id:  isize, start_vertex: isize, logfile: Option<&LogFile>) {
+
+        let mut dfs_stack = Vec::<isize>::new();
+        dfs_stack.push(vertex_id);
+
+        while let Some(cur_vertex) = dfs_stack.pop() {
+
+            // a vertex can be pushed more than once before it is explored
+            if self.is_explored(cur_vertex) {
+                trace!("Vertex {} Already explored -- skipping",cur_vertex);
+                continue;
+            }
+
+            debug!("Iter Outgoing Exploring {} - start vertex is {}",cur_vertex, start_vertex);
+            self.explored.insert(cur_vertex,());
+            write_explored(logfile,cur_vertex);
+            self.add_to_group(cur_vertex,start_vertex);
+
+            for dest_vertex in self.graph.get_outgoing_vertex_ids(cur_vertex).into_iter().rev() {
+                if !self.is_explored(dest_vertex) {
+                    trace!("Iter Adding outgoing to {}",dest_vertex);
+                    dfs_stack.push(dest_vertex);
+                }
+            }
+        }
+
+	}
+
+    pub fn dfs_incoming(&mut self, vertex_id:  isize, logfile: Option<&LogFile>) {
 
         if self.use_iter {
             self.dfs_in_iter(vertex_id,logfile);
@@ -119,12 +193,12 @@ impl<'a> Kosaraju<'a> {
     }
     /// Perform the next level of the Depth First Search using the incoming edges
     /// from a specfic vertex, keeping track of where the search started
-	pub fn dfs_in(&mut self, vertex_id:  isize, logfile: &LogFile) {
+	pub fn dfs_in(&mut self, vertex_id:  isize, logfile: Option<&LogFile>) {
 			
         debug!("Incoming Exploring {}",vertex_id);
         // Set current node to explored
-        self.explored.insert(vertex_id,true);
-        log_writeln!(logfile,"{}",vertex_id);
+        self.explored.insert(vertex_id,());
+        write_explored(logfile,vertex_id);
 
         /*
         // Add this vertex to the group associcated with the 
@@ -136,8 +210,7 @@ impl<'a> Kosaraju<'a> {
         self.add_search_entry(start_vertex,new_len);
         */
 
-        for edge in self.graph.get_incoming_edges(vertex_id) {
-            let source_vertex = edge.source();
+        for source_vertex in self.graph.get_incoming_vertex_ids(vertex_id) {
             trace!("Checking incoming from {}",source_vertex);
             if !self.is_explored(source_vertex) {
                 self.dfs_incoming(source_vertex,logfile);
@@ -151,7 +224,7 @@ impl<'a> Kosaraju<'a> {
 
     /// Perform the next level of the Depth First Search using the incoming edges
     /// from a specfic vertex, keeping track of where the search started
-	pub fn dfs_in_iter(&mut self, vertex_id:  isize, logfile: &LogFile) {
+	pub fn dfs_in_iter(&mut self, vertex_id:  isize, logfile: Option<&LogFile>) {
 
         let mut dfs_stack = Vec::<isize>::new();
         dfs_stack.push(vertex_id);
@@ -180,15 +253,14 @@ impl<'a> Kosaraju<'a> {
             // if not yet explored then process it...
             else {
                 // mark it as explored
-                self.explored.insert(cur_vertex,true);
-                log_writeln!(logfile,"{}",cur_vertex);
+                self.explored.insert(cur_vertex,());
+                write_explored(logfile,cur_vertex);
 
-                let in_vertex : Vec<isize> = self.graph.get_incoming_edges(cur_vertex).iter().map(|e| e.source()).collect() ;
+                let in_vertex : Vec<isize> = self.graph.get_incoming_vertex_ids(cur_vertex);
                 trace!("Incoming edges for loop {:?}",in_vertex);
 
                 // add all the adajacent vertexs with incoming edges that haven't been explored yet
-                for edge in self.graph.get_incoming_edges(cur_vertex).iter().rev() {
-                    let source_vertex = edge.source();
+                for source_vertex in in_vertex.into_iter().rev() {
 
                     trace!("Check source vertex {}",source_vertex);
                     // if the source vertex has not yet been seen, mark it as seen
@@ -215,9 +287,9 @@ impl<'a> Kosaraju<'a> {
 		info!("Starting on incoming DFS");
 		self.finished_order = Vec::<isize>::new();
 		self.start_search = HashMap::<isize,Vec::<isize>>::new();
-		self.explored = HashMap::<isize,bool>::new();
+		self.explored.clear();
 		self.top_search_cnts = HashMap::<isize,usize>::new();
-        let explored_in_log = LogFile::new("kosara_explored_in").unwrap();
+        let explored_in_log = explored_log(self.log_explored,"kosara_explored_in");
 
 		let mut _count : isize = 0;
 		for v in list {
@@ -229,7 +301,7 @@ impl<'a> Kosaraju<'a> {
             
 			if !self.is_explored(vertex) {
 //				self.dfs_incoming(vertex,vertex,0);
-				self.dfs_incoming(vertex,&explored_in_log);
+				self.dfs_incoming(vertex,explored_in_log.as_ref());
 //                assert_eq!(self.finished_order,self.iter_finished_order);
               trace!("Finishing Order {:?}",self.finished_order);
 			}
@@ -240,9 +312,10 @@ impl<'a> Kosaraju<'a> {
 	pub fn dfs_loop_outgoing(&mut self, list: &Vec<isize>,show_progress: bool) {
 		info!("Looping on outgoing DFS {:?}",list);
 		self.start_search = HashMap::<isize,Vec::<isize>>::new();
-		self.explored = HashMap::<isize,bool>::new();
+		self.explored.clear();
 		self.top_search_cnts = HashMap::<isize,usize>::new();
-        let explored_out_log = LogFile::new("kosara_explored_out").unwrap();
+		self.scc_rank.clear();
+        let explored_out_log = explored_log(self.log_explored,"kosara_explored_out");
 
 		let mut _count : isize = 0;
 		for v in list {
@@ -254,13 +327,34 @@ impl<'a> Kosaraju<'a> {
 
 			trace!("OutLoop from {}",vertex);
 			if !self.is_explored(vertex) {
-				self.dfs_outgoing(vertex,vertex,&explored_out_log);
+                // each new search starts a new SCC; record the order they are found in
+                let rank = self.scc_rank.len();
+                self.scc_rank.insert(vertex,rank);
+				self.dfs_outgoing(vertex,vertex,explored_out_log.as_ref());
 			}
 		}
 	}
 
 
-    pub fn find_scc(&mut self) {
+    pub fn get_finish_order(&self) -> Vec<isize> {
+        self.finished_order.iter().cloned().collect()
+
+    }
+
+    pub fn get_top_scc_groups(&self) -> HashMap<isize,Vec<isize>> {
+        let mut result = HashMap::<isize,Vec<isize>>::new();
+        for (group,_count) in &self.top_search_cnts {
+            result.insert(*group,self.get_scc_group(*group));
+        }
+        result
+    }
+
+
+}
+
+impl<'a, G: SearchGraph> SccFinder for Kosaraju<'a, G> {
+
+    fn find_scc(&mut self) {
 
 
         // Performae a DFS on all vertex to define a finshing order for use in the 2nd DFS
@@ -282,60 +376,97 @@ impl<'a> Kosaraju<'a> {
 
     }
 
-    pub fn get_scc_sizes(&self) -> Vec<usize> {
-        let sizes = self.top_search_cnts.iter().map(|(_k,v)| *v).collect();
-        debug!("getscc top search cnts {:?} sizes {:?}",self.top_search_cnts, sizes);
-        sizes
+    fn get_group(&self, vertex_id: isize) -> Option<&isize> {
+
+        self.vertex_scc_map.get(vertex_id)
     }
 
-    pub fn get_finish_order(&self) -> Vec<isize> {
-        self.finished_order.iter().cloned().collect()
+    fn get_scc_rank(&self, vertex_id: isize) -> Option<usize> {
+        self.get_group(vertex_id).and_then(|group| self.scc_rank.get(*group).cloned())
+    }
 
+    fn get_scc_sizes(&self) -> Vec<usize> {
+        let sizes = self.top_search_cnts.iter().map(|(_k,v)| *v).collect();
+        debug!("getscc top search cnts {:?} sizes {:?}",self.top_search_cnts, sizes);
+        sizes
     }
 
-    pub fn get_scc_group(&self,group : isize) -> Vec<isize> {
+    fn get_scc_group(&self,group : isize) -> Vec<isize> {
         let start_group = self.start_search.get(&group).unwrap();
         start_group.iter().cloned().collect()
     }
 
-    pub fn get_top_scc_groups(&self) -> HashMap<isize,Vec<isize>> {
-        let mut result = HashMap::<isize,Vec<isize>>::new();
-        for (group,_count) in &self.top_search_cnts {
-            result.insert(*group,self.get_scc_group(*group));
-        }
-        result
+    fn log_scc_to_files(&self, prefix: &str) {
+        log_scc_groups(&self.start_search,prefix);
     }
+}
 
-    pub fn log_scc_to_files(&self, prefix: &str) {
 
-        let summary_file = LogFile::new(&format!("{}_scc_summary.txt",prefix)[..]).unwrap();
-        let mut hash_vec: Vec<(&isize, &usize)> = self.top_search_cnts.iter().collect();
-        hash_vec.sort_by(|a, b| b.1.cmp(a.1));
+#[cfg(test)]
+mod tests {
+    use crate::dirgraph::DirectedGraph;
+    use crate::graphbuilder::GraphBuilder;
+    use crate::kosaraju::Kosaraju;
+    use crate::log_files::{set_log_dir,set_thread_log_dir};
+    use crate::scc::SccFinder;
 
-        for (group, count) in hash_vec {
-            if count > &1 {
-                log_writeln!(summary_file,"{}  group {}",count,group);
-            }
+    fn test_init() -> DirectedGraph {
+          let _ = env_logger::builder().is_test(true).try_init();
+          let log_dir = std::env::temp_dir().join("sat2_kosaraju_test");
+          set_log_dir(log_dir.to_str().unwrap());
+          DirectedGraph::new()
+    }
+
+    #[test]
+    fn basic_scc() {
+		let mut graph = test_init();
+        let mut g = &mut graph;
+        // 1 -> 2 -> 3 -> 1 and 3 -> 4 -> 5 -> 4
+		g.add_edge(1,2,1);
+		g.add_edge(2,3,1);
+		g.add_edge(3,1,1);
+		g.add_edge(3,4,1);
+		g.add_edge(4,5,1);
+		g.add_edge(5,4,1);
+        for use_iter in [true, false] {
+            let mut k = Kosaraju::new(&graph,use_iter);
+            k.find_scc();
+            let mut sizes = k.get_scc_sizes();
+            sizes.sort();
+            assert_eq!(sizes,vec!(2,3));
+            assert_eq!(k.get_group(1),k.get_group(3));
+            assert_eq!(k.get_group(4),k.get_group(5));
+            assert_ne!(k.get_group(1),k.get_group(4));
+            // {4,5} is a sink so it is found first
+            assert!(k.get_scc_rank(4) < k.get_scc_rank(1));
         }
 
-        let single_scc_file = LogFile::new(&format!("{}/1_single_vertex.scc",prefix)[..]).unwrap();
-        let mut sort_by_count: Vec<(&isize, &Vec<isize>)> = self.start_search.iter().collect();
-        sort_by_count.sort_by(|a, b| b.1.len().cmp(&a.1.len()));
-        for (group_id, vertex_list) in &self.start_search {
-            let length = vertex_list.len();
-            if length > 1 {
-                let scc_file = LogFile::new(&format!("{}/{}_{}.scc",prefix,length,group_id)[..]).unwrap();
-                let mut sorted_list : Vec<&isize> = vertex_list.iter().collect();
-                sorted_list.sort();
-                for v in sorted_list {
-                    log_writeln!(scc_file,"{}",v);
-                }
-            }
-            else {
-                log_writeln!(single_scc_file,"{}",group_id); 
-            }
+        // the explored vertexes are only logged when asked for
+        let log_dir = std::env::temp_dir().join(format!("sat2_kosaraju_explored_{}",std::process::id()));
+        set_thread_log_dir(Some(log_dir.to_str().unwrap()));
+        Kosaraju::new(&graph,true).find_scc();
+        assert!(!log_dir.join("kosara_explored_out").exists());
+        Kosaraju::new(&graph,true).log_explored(true).find_scc();
+        let explored = std::fs::read_to_string(log_dir.join("kosara_explored_out")).unwrap();
+        set_thread_log_dir(None);
+        std::fs::remove_dir_all(&log_dir).unwrap();
+        assert_eq!(explored.lines().count(),5);
+    }
+
+    #[test]
+    fn long_implication_chain() {
+        // a cycle long enough that the recursive search would overflow the default stack
+        const CHAIN_LENGTH : isize = 300_000;
+		let mut graph = test_init();
+        let mut g = &mut graph;
+        for i in 1..CHAIN_LENGTH {
+            g.add_edge(i,i+1,1);
         }
-        
+        g.add_edge(CHAIN_LENGTH,1,1);
+
+        let mut k = Kosaraju::new(&graph,true);
+        k.find_scc();
+        assert_eq!(k.get_scc_sizes(),vec!(CHAIN_LENGTH as usize));
+        assert_eq!(k.get_group(1),k.get_group(CHAIN_LENGTH));
     }
 }
-
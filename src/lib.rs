@@ -0,0 +1,64 @@
+//! 2-SAT solver using the strongly connected components of the implication graph.
+//!
+//! Each clause (a OR b) becomes the two implications -a -> b and -b -> a.  The formula can be
+//! satisfied unless some variable x is in the same SCC as -x.
+//!
+//! The simplest way to use the crate is through [`Solver`]:
+//!
+//! ```
+//! use sat2::{Formula,Literal,Solver};
+//!
+//! # fn main() -> Result<(),String> {
+//! let x1 = Literal::new(1)?;
+//! let x2 = Literal::new(2)?;
+//! let mut formula = Formula::new(2);
+//! formula.add_clause(x1,x2);
+//! formula.add_clause(-x1,x2);
+//! let solution = Solver::new().solve(&formula);
+//! assert_eq!(solution.value(x2),Some(true));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The graph building ([`GraphBuilder`]), graph search ([`SearchGraph`]) and SCC
+//! ([`SccFinder`]) pieces don't depend on the 2-SAT front end and can be used on
+//! their own with [`DirectedGraph`] or any other graph that implements the traits.
+
+#[macro_use]
+pub mod log_files;
+pub mod assume;
+pub mod backbone;
+pub mod batch;
+#[cfg(test)]
+mod bruteforce;
+pub mod condensation;
+pub mod conflict;
+pub mod csrgraph;
+pub mod dirgraph;
+pub mod driver;
+pub mod enumerate;
+pub mod export;
+pub mod gabow;
+pub mod generator;
+pub mod graphbuilder;
+pub mod incremental;
+pub mod input;
+pub mod kosaraju;
+pub mod literal;
+pub mod parse;
+pub mod query;
+pub mod report;
+pub mod scc;
+pub mod searchgraph;
+pub mod simplify;
+pub mod solver;
+pub mod tarjan;
+pub mod unsatcore;
+
+pub use crate::dirgraph::DirectedGraph;
+pub use crate::graphbuilder::GraphBuilder;
+pub use crate::literal::{Literal,Variable};
+pub use crate::parse::{Clause,Formula,ParseError};
+pub use crate::scc::{SccAlgorithm,SccFinder};
+pub use crate::searchgraph::SearchGraph;
+pub use crate::solver::{Solution,Solver};
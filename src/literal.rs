@@ -0,0 +1,161 @@
+use std::fmt;
+use std::ops::Neg;
+
+
+/// A variable of the formula, numbered from 1
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash,PartialOrd,Ord)]
+pub struct Variable(usize);
+
+/// A variable or its negation.  Stored as the signed variable number (e.g. -3 is variable 3
+/// being false), which is also the id of the literal's vertex in the implication graph
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash,PartialOrd,Ord)]
+pub struct Literal(isize);
+
+
+impl Variable {
+
+    /// Creates a variable from its number, which must be at least 1
+    pub fn new(number: usize) -> Result<Variable,String> {
+        if number == 0 || number > isize::MAX as usize {
+            return Err(format!("invalid variable {} - variables are numbered from 1",number));
+        }
+        Ok(Variable(number))
+    }
+
+    /// The variable for an index into an assignment (i.e. index 0 is variable 1)
+    pub fn from_index(index: usize) -> Variable {
+        Variable(index + 1)
+    }
+
+    pub fn number(self) -> usize {
+        self.0
+    }
+
+    /// Position of the variable in an assignment
+    pub fn index(self) -> usize {
+        self.0 - 1
+    }
+
+    pub fn positive(self) -> Literal {
+        Literal(self.0 as isize)
+    }
+
+    pub fn negative(self) -> Literal {
+        Literal(0-self.0 as isize)
+    }
+
+    /// The literal for the variable having the given value
+    pub fn literal(self, value: bool) -> Literal {
+        if value { self.positive() } else { self.negative() }
+    }
+}
+
+impl fmt::Display for Variable {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+
+impl Literal {
+
+    /// Creates a literal from its signed variable number, which can't be 0
+    pub fn new(value: isize) -> Result<Literal,String> {
+        if value == 0 || value == isize::MIN {
+            return Err(format!("invalid literal {}",value));
+        }
+        Ok(Literal(value))
+    }
+
+    pub fn var(self) -> Variable {
+        Variable(self.0.unsigned_abs())
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    pub fn negate(self) -> Literal {
+        Literal(0-self.0)
+    }
+
+    /// The signed variable number, which is also the literal's vertex id in the implication graph
+    pub fn value(self) -> isize {
+        self.0
+    }
+
+    /// Dense index of the literal;  2*(v-1) for variable v and 2*(v-1)+1 for its negation
+    pub fn index(self) -> usize {
+        let var_index = self.var().index() * 2;
+        if self.is_positive() { var_index } else { var_index + 1 }
+    }
+
+    /// Converts a dense index back to its literal
+    pub fn from_index(index: usize) -> Literal {
+        Variable::from_index(index / 2).literal(index % 2 == 0)
+    }
+
+    /// Whether the literal is true in an assignment (entry i is the value of variable i+1),
+    /// or None if the assignment doesn't include the variable
+    pub fn is_true(self, assignment: &[bool]) -> Option<bool> {
+        assignment.get(self.var().index()).map(|value| *value == self.is_positive())
+    }
+}
+
+impl Neg for Literal {
+    type Output = Literal;
+
+    fn neg(self) -> Literal {
+        self.negate()
+    }
+}
+
+impl TryFrom<isize> for Literal {
+    type Error = String;
+
+    fn try_from(value: isize) -> Result<Literal,String> {
+        Literal::new(value)
+    }
+}
+
+impl From<Literal> for isize {
+
+    fn from(literal: Literal) -> isize {
+        literal.0
+    }
+}
+
+impl fmt::Display for Literal {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::literal::{Literal,Variable};
+
+    #[test]
+    fn literals() {
+        assert!(Literal::new(0).is_err());
+        assert!(Variable::new(0).is_err());
+        let x3 = Variable::new(3).unwrap();
+        let not_x3 = Literal::new(-3).unwrap();
+        assert_eq!(x3.negative(),not_x3);
+        assert_eq!(-not_x3,x3.positive());
+        assert_eq!(not_x3.var(),x3);
+        assert!(!not_x3.is_positive());
+        assert_eq!(x3.positive().index(),4);
+        assert_eq!(not_x3.index(),5);
+        for index in 0..10 {
+            assert_eq!(Literal::from_index(index).index(),index);
+        }
+        assert_eq!(not_x3.is_true(&[true,true,false]),Some(true));
+        assert_eq!(not_x3.is_true(&[true]),None);
+        assert_eq!(not_x3.to_string(),"-3");
+        assert_eq!(isize::from(not_x3),-3);
+    }
+}
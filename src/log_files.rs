@@ -4,6 +4,7 @@ use log::{ info, error };
 use std::io::{Write}; 
 use lazy_static::lazy_static;
 use std::sync::Mutex;
+use std::cell::RefCell;
 
 
 #[derive(Debug)]
@@ -16,12 +17,26 @@ lazy_static! {
           static ref MY_GLOBAL: Mutex<String> = Mutex::new(".".to_string());
 }
 
+thread_local! {
+    /// log directory used by the current thread instead of the shared one
+    static THREAD_LOG_DIR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
 pub fn set_log_dir(log_dir_name: &str) {
     *MY_GLOBAL.lock().unwrap() = log_dir_name.to_string();
 }
 
+/// Sets (or with None, clears) the log directory for just the current thread, so that
+/// threads working on different inputs don't write over each other's log files
+pub fn set_thread_log_dir(log_dir_name: Option<&str>) {
+    THREAD_LOG_DIR.with(|dir| *dir.borrow_mut() = log_dir_name.map(|name| name.to_string()));
+}
+
 pub fn get_log_dir() -> String {
-    MY_GLOBAL.lock().unwrap().to_string()
+    match THREAD_LOG_DIR.with(|dir| dir.borrow().clone()) {
+        Some(log_dir_name) => log_dir_name,
+        None => MY_GLOBAL.lock().unwrap().to_string(),
+    }
 }
 
 impl LogFile {
@@ -1,98 +1,154 @@
-#[macro_use]
-mod log_files;
 mod cmd_line;
-mod dirgraph;
-mod graphbuilder;
-mod kosaraju;
-mod parse;
 
-
-use crate::cmd_line::CommandArgs;
-use crate::kosaraju::Kosaraju;
-use crate::dirgraph::DirectedGraph;
-//use crate::graphbuilder::GraphBuilder;
-use crate::parse::read_constraints;
+use crate::cmd_line::{CommandArgs,Commands,OutputFormat};
+use sat2::scc::SccAlgorithm;
+use sat2::literal::Literal;
+use sat2::parse::ParseMode;
+use sat2::generator::Generator;
+use sat2::export::ExportFormat;
+use sat2::batch::{expand_inputs,run_batch,print_batch};
+use sat2::driver::{answer_queries,run,simplify_file,solve_file,write_formula,RunError,RunOptions,SolveOptions};
 
 
 use clap::Parser;
-use log::{ info, debug };
+use log::debug;
 
 use std::process;
-use std::path::Path;
-use std::fs::File;
 use std::thread;
 
 use std::fs;
-use crate::log_files::{LogFile,set_log_dir};
+use sat2::log_files::set_log_dir;
 
 
 
-fn main() {
+// exit codes for the different failures
+const EXIT_OPEN_FAILED: i32 = 3;
+const EXIT_PARSE_FAILED: i32 = 4;
+const EXIT_INVALID_ASSUMPTION: i32 = 5;
+const EXIT_WRITE_FAILED: i32 = 6;
+const EXIT_BATCH_FAILED: i32 = 7;
+const EXIT_USAGE: i32 = 2;
 
-    env_logger::init();
 
-    set_log_dir(&"log");
-    fs::remove_dir_all("log").ok();
+/// Prints why the command failed and exits with the matching code
+fn exit_on_error(result: Result<(),RunError>) {
 
-    let cmd_line = CommandArgs::parse();
-    debug!("The Command Line, {:?}!",cmd_line);
+    if let Err(why) = result {
+        eprintln!("{}",why);
+        process::exit(match why {
+            RunError::Usage(_) => EXIT_USAGE,
+            RunError::Open(_) => EXIT_OPEN_FAILED,
+            RunError::Parse(_) => EXIT_PARSE_FAILED,
+            RunError::InvalidAssumption(_) => EXIT_INVALID_ASSUMPTION,
+            RunError::Write(_) => EXIT_WRITE_FAILED,
+        });
+    }
+}
 
-    // Create a path to the desired file
-    let path = Path::new(&cmd_line.filename);
-    let display = path.display();
+/// Collects the options for solving a single file, checking the ones that can't be used together
+fn run_options(cmd_line: &CommandArgs, solve: SolveOptions) -> Result<RunOptions,RunError> {
 
-    // Open the path in read-only mode, returns `io::Result<File>`
-    let mut file = match File::open(&path) {
-        Err(why) => panic!("couldn't open {}: {}", display, why),
-        Ok(file) => file,
+    let json = cmd_line.format == OutputFormat::Json;
+    if json && cmd_line.export.as_deref() == Some("-") {
+        return Err(RunError::Usage("--export - can't be used with --format json as both write to stdout".to_string()));
+    }
+    let export = match &cmd_line.export {
+        Some(name) => match cmd_line.export_format.or_else(|| ExportFormat::from_file_name(name)) {
+            Some(format) => Some((name.clone(),format)),
+            None => return Err(RunError::Usage(format!("can't tell the export format from {}, use --export-format",name))),
+        },
+        None => None,
     };
+    let assumptions = cmd_line.assume.iter().map(|value| Literal::new(*value)).collect::<Result<Vec<Literal>,String>>()
+        .map_err(|why| RunError::InvalidAssumption(format!("Assume: {}",why)))?;
+
+    Ok(RunOptions {
+        solve,
+        show_assignment: cmd_line.assignment,
+        explain: cmd_line.explain,
+        core: cmd_line.core.clone(),
+        show_graph: cmd_line.show_graph,
+        count_solutions: cmd_line.count_solutions,
+        solution_limit: cmd_line.solution_limit,
+        all_solutions: cmd_line.all_solutions,
+        backbone: cmd_line.backbone,
+        export,
+        export_scc: cmd_line.export_scc,
+        json,
+        assumptions,
+    })
+}
 
+fn main() {
 
-	let mut graph = DirectedGraph::new();
-    let num_variables = read_constraints(&mut file, &mut graph);
-
-    if cmd_line.show_graph {
-        println!("DirGraph");
-        graph.print_graph();
-        process::exit(0);
-        
-    }
-    graph.log_graph("kosara");
-
-	let child = thread::Builder::new().name("Working Thread".to_string()).stack_size(512 * 1024 * 1024).spawn(move || { 
+    env_logger::init();
 
-	   // code to be executed in thread
+    set_log_dir(&"log");
+    fs::remove_dir_all("log").ok();
 
-        let _handle = thread::current();
+    let cmd_line = CommandArgs::parse();
+    debug!("The Command Line, {:?}!",cmd_line);
 
-        let mut k = Kosaraju::new(&mut graph,true);
-        k.find_scc();
-        k.log_scc_to_files("kosara");
-        // sort in reverse order
-        let mut scc_sizes = k.get_scc_sizes();
-        scc_sizes.sort_by(|a, b| b.cmp(a));
-        info!("K sizes {:?}",scc_sizes);
+    match &cmd_line.command {
+        Some(Commands::Gen(args)) => {
+            let num_clauses = args.clauses.unwrap_or(args.variables);
+            exit_on_error(Generator::new(args.variables,args.seed).generate(args.mode,num_clauses,args.cycle_length)
+                .map_err(RunError::Usage)
+                .and_then(|clauses| write_formula(&args.output,args.variables,&clauses,args.dimacs)));
+            return;
+        },
+        Some(Commands::Simplify(args)) => {
+            exit_on_error(simplify_file(&args.input,args.input_format,&args.output,args.dimacs));
+            return;
+        },
+        Some(Commands::Query(args)) => {
+            exit_on_error(answer_queries(&args.formula,&args.queries,args.input_format));
+            return;
+        },
+        None => {},
+    }
 
-        let mut constraint_met = true;
-        for i in 0..num_variables {
-            let vertex_id = (i+1) as isize;
-            let not_vertex_id = 0-vertex_id;
-            if k.get_group(vertex_id) == k.get_group(not_vertex_id) {
-                constraint_met = false;
+    let (files, batch) = match expand_inputs(&cmd_line.filenames) {
+        Ok(expanded) => expanded,
+        Err(why) => {
+            eprintln!("{}",why);
+            process::exit(EXIT_OPEN_FAILED);
+        },
+    };
 
-                break;
-            }
-        }
-        if constraint_met {
-            println!("1");
+    let solve = SolveOptions {
+        input_format: cmd_line.input_format,
+        parse_mode: if cmd_line.lenient { ParseMode::Lenient } else { ParseMode::Strict },
+        algorithm: cmd_line.algorithm,
+        use_iter: !cmd_line.recursive,
+        use_csr: cmd_line.csr,
+        simplify: cmd_line.simplify,
+        log_explored: cmd_line.log_explored,
+    };
+    // the recursive version of Kosaraju needs a much larger stack than the default one
+    let stack_size = if solve.algorithm == SccAlgorithm::Kosaraju && !solve.use_iter { Some(512 * 1024 * 1024) } else { None };
+
+    if batch {
+        if cmd_line.explain || cmd_line.core.is_some() || !cmd_line.assume.is_empty() || cmd_line.show_graph || cmd_line.assignment
+            || cmd_line.count_solutions || cmd_line.all_solutions.is_some() || cmd_line.backbone || cmd_line.export.is_some()
+            || cmd_line.format == OutputFormat::Json {
+            eprintln!("--explain, --core, --assume, --show-graph, --assignment, --count-solutions, --all-solutions, --backbone, --export and --format json can only be used with a single input file");
+            process::exit(EXIT_USAGE);
         }
-        else {
-            println!("0");
+        let results = run_batch(&files,cmd_line.jobs,stack_size,|name| solve_file(name,solve));
+        print_batch(&results);
+        if results.iter().any(|result| result.outcome.is_err()) {
+            process::exit(EXIT_BATCH_FAILED);
         }
+        return;
+    }
 
-
-	}).unwrap(); 
-	child.join().unwrap();
-
+    let options = run_options(&cmd_line,solve);
+    let file = files[0].clone();
+    let find_solution = move || options.and_then(|options| run(&file,&options));
+    let result = match stack_size {
+        Some(size) => thread::Builder::new().name("Working Thread".to_string()).stack_size(size).spawn(find_solution).unwrap().join().unwrap(),
+        None => find_solution(),
+    };
+    exit_on_error(result);
 }
-
@@ -1,77 +1,634 @@
-use std::fs::File;
-use std::io::{BufReader,BufRead};
-use regex::Regex;
-use log::{  info , error, trace };
+use std::io::{self,BufRead,Cursor,Read,Write};
+use std::collections::HashMap;
+use std::fmt;
+use clap::ValueEnum;
+use log::{  info , warn, debug, trace };
 use crate::graphbuilder::GraphBuilder;
+use crate::literal::{Literal,Variable};
 
 
-// First line is number of vertexes and number of edges
-// e.g.    
+/// The supported input file formats
+#[derive(Debug,Clone,Copy,PartialEq,Eq,ValueEnum)]
+pub enum InputFormat {
+    /// detect the format from the start of the file
+    Auto,
+    /// number of variables on the first line followed by one clause (two literals) per line
+    Course,
+    /// DIMACS CNF  ('p cnf' header, 'c' comments and clauses terminated by 0)
+    Dimacs,
+}
+
+/// How problems in the input are handled
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum ParseMode {
+    /// any malformed line or out of range literal is an error
+    Strict,
+    /// malformed lines are skipped, the variable count is extended to cover out of
+    /// range literals, and each problem is kept as a warning
+    Lenient,
+}
+
+
+/// The kinds of problems found while reading the input
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum ParseErrorKind {
+    /// the file couldn't be read
+    Io,
+    /// the variable count (or DIMACS problem line) is missing or invalid
+    Header,
+    /// a line that isn't a clause
+    MalformedClause,
+    /// a literal that isn't a number
+    InvalidLiteral,
+    /// a literal of 0 (other than the DIMACS clause terminator)
+    ZeroLiteral,
+    /// a literal for a variable larger than the declared number of variables
+    LiteralOutOfRange,
+    /// a DIMACS clause with more than two literals
+    TooManyLiterals,
+    /// a DIMACS clause with no literals
+    EmptyClause,
+}
+
+/// A problem found while reading the input, with the line and column (both starting
+/// at 1) where it was found.  A column of 0 means the problem is with the whole line
+#[derive(Debug,Clone,PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+
+    pub fn new(kind: ParseErrorKind, line: usize, column: usize, message: String) -> ParseError {
+        ParseError { kind, line, column, message }
+    }
+}
+
+impl fmt::Display for ParseError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.column > 0 {
+            write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+        }
+        else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+
+/// A single constraint (first OR second) read from the input
+#[derive(Debug,Clone,PartialEq)]
+pub struct Clause {
+    pub first: Literal,
+    pub second: Literal,
+    /// line number of the input file the clause was read from (0 if it wasn't read from a file)
+    pub line: usize,
+    /// the comment after '#' on the clause's line, if there was one
+    pub label: Option<String>,
+}
+
+impl Clause {
+
+    pub fn new(first: Literal, second: Literal) -> Clause {
+        Clause { first, second, line: 0, label: None }
+    }
+}
+
+impl fmt::Display for Clause {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({} {})", self.first, self.second)
+    }
+}
+
+/// The constraints read from the input along with where each came from.  The implication
+/// graph is built from the clauses by build_graph, or build_graph_with_provenance when the
+/// edges need to be traced back to their clauses
+#[derive(Debug,Clone,Default)]
+pub struct Formula {
+    pub num_variables: usize,
+    pub clauses: Vec<Clause>,
+    /// name of the file the clauses were read from
+    pub source: Option<String>,
+    /// maps an edge id in the implication graph to the index of the clause that created it
+    /// (filled in by build_graph_with_provenance)
+    pub edge_clause: HashMap<usize,usize>,
+    /// problems that were skipped over when reading in lenient mode
+    pub warnings: Vec<ParseError>,
+}
+
+impl Formula {
+
+    /// Creates a formula with no clauses over the variables 1..=num_variables
+    pub fn new(num_variables: usize) -> Formula {
+        Formula { num_variables, ..Formula::default() }
+    }
+
+    /// Adds the clause (first OR second), increasing the number of variables if needed,
+    /// and returns its index.  A unit clause is added with both literals the same
+    pub fn add_clause(&mut self, first: Literal, second: Literal) -> usize {
+        self.push_clause(Clause::new(first,second))
+    }
+
+    /// Adds a clause along with where it came from and returns its index
+    pub fn push_clause(&mut self, clause: Clause) -> usize {
+        self.num_variables = self.num_variables.max(clause.first.var().number()).max(clause.second.var().number());
+        self.clauses.push(clause);
+        self.clauses.len()-1
+    }
+
+    /// Describes where a clause came from (e.g. "a.txt line 5 (x implies y)"), for messages
+    pub fn clause_source(&self, clause_index: usize) -> String {
+        let clause = &self.clauses[clause_index];
+        let mut text = match (&self.source, clause.line) {
+            (Some(source), 0) => source.clone(),
+            (Some(source), line) => format!("{} line {}",source,line),
+            (None, 0) => format!("clause {}",clause_index+1),
+            (None, line) => format!("line {}",line),
+        };
+        if let Some(label) = &clause.label {
+            text.push_str(&format!(" ({})",label));
+        }
+        text
+    }
+
+    /// Adds the vertexes for the variables and the edges for the implications of each clause
+    /// to the graph.  A unit clause (l OR l) only needs the one implication -l -> l
+    pub fn build_graph<F: GraphBuilder>(&self, graph_functions: F) {
+        self.add_to_graph(graph_functions,|_, _| {});
+    }
+
+    /// Builds the graph like build_graph and keeps the map from each edge id to the index of
+    /// the clause that created it in edge_clause, which explaining a conflict needs
+    pub fn build_graph_with_provenance<F: GraphBuilder>(&mut self, graph_functions: F) {
+        let mut edge_clause = HashMap::<usize,usize>::new();
+        self.add_to_graph(graph_functions,|edge_id, clause_index| {
+            edge_clause.insert(edge_id,clause_index);
+        });
+        self.edge_clause = edge_clause;
+    }
+
+    /// Adds the vertexes and edges, passing the id of each edge and the index of its clause to record
+    fn add_to_graph<F: GraphBuilder, R: FnMut(usize,usize)>(&self, mut graph_functions: F, mut record: R) {
+        info!("Setting up {} vertexes",self.num_variables * 2);
+        graph_functions.reserve(self.num_variables * 2,self.clauses.len() * 2);
+        for index in 0..self.num_variables {
+            graph_functions.add_variable(Variable::from_index(index));
+        }
+        for (clause_index, clause) in self.clauses.iter().enumerate() {
+            if let Some(edge_id) = graph_functions.add_implication(clause.first.negate(), clause.second) {
+                record(edge_id,clause_index);
+            }
+            if clause.first != clause.second {
+                if let Some(edge_id) = graph_functions.add_implication(clause.second.negate(), clause.first) {
+                    record(edge_id,clause_index);
+                }
+            }
+        }
+        graph_functions.finish();
+    }
+
+    /// Records a problem;  in strict mode it's returned as an error, in lenient mode
+    /// it's kept as a warning so the caller can skip past it
+    fn report(&mut self, mode: ParseMode, error: ParseError) -> Result<(),ParseError> {
+        match mode {
+            ParseMode::Strict => Err(error),
+            ParseMode::Lenient => {
+                warn!("{}",error);
+                self.warnings.push(error);
+                Ok(())
+            },
+        }
+    }
+
+    /// Checks each (column, literal) is in range.  Out of range literals are an error in
+    /// strict mode, otherwise the number of variables is increased to cover them
+    fn check_range(&mut self, mode: ParseMode, literals: &[(usize,Literal)], line: usize, declared: bool) -> Result<(),ParseError> {
+        for (column, literal) in literals {
+            let var = literal.var().number();
+            if var > self.num_variables {
+                if declared {
+                    self.report(mode, ParseError::new(ParseErrorKind::LiteralOutOfRange, line, *column,
+                        format!("literal {} is larger than the number of variables ({})",literal,self.num_variables)))?;
+                }
+                self.num_variables = var;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// Splits a line into its whitespace separated fields, each with the column it starts at
+fn fields(line: &str) -> Vec<(usize,&str)> {
+    let mut result = Vec::<(usize,&str)>::new();
+    let mut start = None;
+    for (column, (index, c)) in line.char_indices().enumerate() {
+        match (c.is_whitespace(), start) {
+            (true, Some((field_column, field_index))) => {
+                result.push((field_column, &line[field_index..index]));
+                start = None;
+            },
+            (false, None) => start = Some((column+1, index)),
+            _ => {},
+        }
+    }
+    if let Some((field_column, field_index)) = start {
+        result.push((field_column, &line[field_index..]));
+    }
+    result
+}
+
+fn parse_literal(text: &str, line: usize, column: usize) -> Result<isize,ParseError> {
+    text.parse::<isize>().map_err(|_|
+        ParseError::new(ParseErrorKind::InvalidLiteral, line, column, format!("invalid literal '{}'",text)))
+}
+
+fn io_error(why: io::Error, line: usize) -> ParseError {
+    ParseError::new(ParseErrorKind::Io, line, 0, why.to_string())
+}
+
+/// Splits the '#' comment off the end of a line, returning the rest of the line and the comment
+fn split_comment(line: &str) -> (&str, Option<String>) {
+    match line.split_once('#') {
+        Some((data, comment)) if !comment.trim().is_empty() => (data, Some(comment.trim().to_string())),
+        Some((data, _)) => (data, None),
+        None => (line, None),
+    }
+}
+
+
+// First line is the number of variables followed by one clause per line.  Anything
+// after a '#' is a comment, which is kept as the label of the clause on that line
+// e.g.
 //
-// 1   2,8   3,6
-// 2   1,8  3, 4
-// 3   1,6, 2, 4
-pub fn read_constraints<F> ( file: & mut File,  mut graph_functions: F) -> usize
-where F: GraphBuilder,
+// 3
+// 1 2   # x1 or x2
+// -1 3
+// -2 -3
+pub fn read_constraints<R> ( reader: R, mode: ParseMode) -> Result<Formula,ParseError>
+where R: BufRead,
 {
 
-    //open the file
-    let mut reader = BufReader::new(file);
-    let mut num_variables = 0;
+    let mut formula = Formula::default();
+    let mut header_read = false;
+    let mut declared = false;
+
+    for (index, line) in reader.lines().enumerate() {
+		let line_count = index + 1;
+		let line_data = line.map_err(|why| io_error(why,line_count))?;
+        trace!("Proccesing Line {} - ({})",line_count,line_data);
+        if line_count % 10000 == 0 {
+            info!("Proccesing Line {} - ({})",line_count,line_data);
+        }
+
+        let (clause_data, label) = split_comment(&line_data);
+        let line_fields = fields(clause_data);
+        if line_fields.is_empty() {
+            continue;
+        }
+
+        if !header_read {
+            header_read = true;
+            if line_fields.len() == 1 {
+                if let Ok(num_vars) = line_fields[0].1.parse::<usize>() {
+                    formula.num_variables = num_vars;
+                    declared = true;
+                    continue;
+                }
+            }
+            // when lenient the line is read as a clause, with the variables added as they're found
+            formula.report(mode, ParseError::new(ParseErrorKind::Header, line_count, line_fields[0].0,
+                format!("expected the number of variables but found '{}'",clause_data.trim())))?;
+        }
+
+        if line_fields.len() != 2 {
+            formula.report(mode, ParseError::new(ParseErrorKind::MalformedClause, line_count, 0,
+                format!("expected a clause with 2 literals but found '{}'",clause_data.trim())))?;
+            continue;
+        }
+
+        let mut literals = Vec::<(usize,Literal)>::new();
+        for (column, text) in &line_fields {
+            let literal = parse_literal(text,line_count,*column).and_then(|value|
+                Literal::new(value).map_err(|_|
+                    ParseError::new(ParseErrorKind::ZeroLiteral, line_count, *column, "literal can't be 0".to_string())));
+            match literal {
+                Ok(literal) => literals.push((*column,literal)),
+                Err(error) => {
+                    formula.report(mode,error)?;
+                    break;
+                },
+            }
+        }
+        if literals.len() != 2 {
+            continue;
+        }
 
-	let mut _line_count = 0;
-    let mut line_data = String::new();
-    if let Err(error) = reader.read_line(&mut line_data) {
-        error!("Error reading first line {}",error);
+        formula.check_range(mode,&literals,line_count,declared)?;
+        formula.push_clause(Clause { first: literals[0].1, second: literals[1].1, line: line_count, label });
     }
-    let re_first_line = Regex::new(r"^\s*(?P<num_vars>\d+)([^\d]*$|$)").unwrap();
-    if let Some(caps) = re_first_line.captures(&line_data) {
-        let num_vars_text = caps.name("num_vars").map_or("", |m| m.as_str());
-        let num_vars = num_vars_text.parse::<usize>().unwrap() as isize;
-        num_variables = num_vars as usize;
-        
-        info!("Setting up {} vertexes",num_vars);
-        for i in 0..num_vars {
-            let vertex_id = i+1;
-            let _ = graph_functions.add_vertex(vertex_id);
-            let _ = graph_functions.add_vertex(0-vertex_id);
+
+    if !header_read {
+        formula.report(mode, ParseError::new(ParseErrorKind::Header, 1, 0, "no number of variables found".to_string()))?;
+    }
+    Ok(formula)
+}
+
+
+/// Writes clauses out in the same format read by read_constraints; the number of
+/// variables on the first line followed by one clause per line, with the label as a comment
+pub fn write_clauses<W> (out: &mut W, num_variables: usize, clauses: &[Clause]) -> io::Result<()>
+where W: Write,
+{
+    writeln!(out,"{}",num_variables)?;
+    for clause in clauses {
+        match &clause.label {
+            Some(label) => writeln!(out,"{} {}  # {}",clause.first,clause.second,label)?,
+            None => writeln!(out,"{} {}",clause.first,clause.second)?,
         }
     }
-    else {
-        error!("Not able to read line {} correctly {}",_line_count,line_data);
+    Ok(())
+}
+
+/// Writes clauses out as DIMACS CNF, with a unit clause (l OR l) written as just l
+pub fn write_dimacs<W> (out: &mut W, num_variables: usize, clauses: &[Clause]) -> io::Result<()>
+where W: Write,
+{
+    writeln!(out,"p cnf {} {}",num_variables,clauses.len())?;
+    for clause in clauses {
+        if clause.first == clause.second {
+            writeln!(out,"{} 0",clause.first)?;
+        }
+        else {
+            writeln!(out,"{} {} 0",clause.first,clause.second)?;
+        }
     }
-    _line_count += 1;	
+    Ok(())
+}
 
-    let mut vertex_count = 0;
-    for line in reader.lines() {
-		_line_count += 1;	
-		vertex_count += 1;	
-		let line_data = line.unwrap();
-        trace!("Proccesing Line {} - ({})",_line_count,line_data);
-        if _line_count % 10000 == 0 {
-            info!("Proccesing Line {} - ({})",_line_count,line_data);
+
+/// A reader with the lines already read by detect_format put back in front of it
+pub type Rewound<R> = io::Chain<Cursor<Vec<u8>>,R>;
+
+/// Works out the format of the input from its first non blank line (a DIMACS file starts
+/// with either a comment or the problem line).  Returns the format along with a reader
+/// that still starts at the beginning of the input
+pub fn detect_format<R: BufRead>(mut reader: R) -> io::Result<(InputFormat, Rewound<R>)> {
+
+    let mut format = InputFormat::Course;
+    let mut consumed = Vec::<u8>::new();
+    loop {
+        let start = consumed.len();
+        if reader.read_until(b'\n',&mut consumed)? == 0 {
+            break;
+        }
+        let text = String::from_utf8_lossy(&consumed[start..]);
+        let text = text.trim_start();
+        if text.is_empty() {
+            continue;
         }
+        if text.starts_with('c') || text.starts_with('p') {
+            format = InputFormat::Dimacs;
+        }
+        break;
+    }
+    debug!("Detected input format {:?}",format);
+    Ok((format, Cursor::new(consumed).chain(reader)))
+}
 
-        let re_constraint = Regex::new(r"^\s*(?P<source>(-*)(\d+))\s+(?P<dest>(-*)(\d+)).*$").unwrap();
-        if let Some(caps) = re_constraint.captures(&line_data) {
+/// Reads the input in the given format (detecting it if needed) into a Formula and builds
+/// its implication graph, keeping the map from edges to clauses in the formula
+pub fn read_input<R,F> (reader: R, format: InputFormat, mode: ParseMode, graph_functions: F) -> Result<Formula,ParseError>
+where R: BufRead,
+      F: GraphBuilder,
+{
+    let mut formula = parse_formula(reader,format,mode)?;
+    formula.build_graph_with_provenance(graph_functions);
+    Ok(formula)
+}
 
-            let text_source = caps.name("source").map_or("", |m| m.as_str());
-            trace!("Text_source  = {} caps {:?}",text_source,caps);
-            let source = text_source.parse::<isize>().unwrap();
+/// Reads the input in the given format (detecting it if needed) into a Formula without building a graph
+pub fn parse_formula<R: BufRead>(reader: R, format: InputFormat, mode: ParseMode) -> Result<Formula,ParseError> {
+    if format != InputFormat::Auto {
+        return read_detected(reader,format,mode);
+    }
+    let (format, reader) = detect_format(reader).map_err(|why| io_error(why,1))?;
+    read_detected(reader,format,mode)
+}
 
-            let text_dest = caps.name("dest").map_or("", |m| m.as_str());
-            trace!("Text_dest  = {} caps {:?}",text_dest,caps);
-            let dest = text_dest.parse::<isize>().unwrap();
+fn read_detected<R: BufRead> (reader: R, format: InputFormat, mode: ParseMode) -> Result<Formula,ParseError> {
+    match format {
+        InputFormat::Dimacs => read_dimacs(reader,mode),
+        _ => read_constraints(reader,mode),
+    }
+}
 
-            // the edges for each implication 
-            graph_functions.add_edge(0-source, dest,1);
-            graph_functions.add_edge(0-dest, source,1);
+/// Adds a clause read from a DIMACS file to the formula.  Clauses with more than 2
+/// literals can't be solved so they're an error even when lenient
+fn add_dimacs_clause(formula: &mut Formula, mode: ParseMode, literals: &[(usize,Literal)],
+                     start: (usize,usize), declared: bool) -> Result<(),ParseError> {
+    let (line, column) = start;
+    let clause_number = formula.clauses.len() + 1;
+    match literals.len() {
+        0 => {
+            return formula.report(mode, ParseError::new(ParseErrorKind::EmptyClause, line, column,
+                format!("clause {} is empty",clause_number)));
+        },
+        1 | 2 => {},
+        count => {
+            let text : Vec<String> = literals.iter().map(|(_,l)| l.to_string()).collect();
+            return Err(ParseError::new(ParseErrorKind::TooManyLiterals, line, column,
+                format!("clause {} has {} literals ({}) - only clauses with at most 2 literals are supported",
+                        clause_number,count,text.join(" "))));
+        },
+    }
+    formula.check_range(mode,literals,line,declared)?;
+    formula.push_clause(Clause { first: literals[0].1, second: literals[literals.len()-1].1, line, label: None });
+    Ok(())
+}
+
+/// Reads a DIMACS CNF file;  'c' lines are comments, the 'p cnf <variables> <clauses>' line
+/// gives the number of variables, and each clause is a list of literals terminated by 0
+/// (which may span more than one line)
+pub fn read_dimacs<R> (reader: R, mode: ParseMode) -> Result<Formula,ParseError>
+where R: BufRead,
+{
+    let mut formula = Formula::default();
+    let mut declared_clauses = None;
+    let mut literals = Vec::<(usize,Literal)>::new();
+    // line and column where the current clause started
+    let mut clause_start = (0,0);
+    // the current clause has a bad literal, so the rest of it (up to its 0) is dropped
+    let mut skipping = false;
 
+    for (index, line) in reader.lines().enumerate() {
+        let line_count = index + 1;
+        let line_data = line.map_err(|why| io_error(why,line_count))?;
+        let line_fields = fields(&line_data);
+
+        if line_fields.is_empty() || line_fields[0].1.starts_with('c') {
+            continue;
         }
-        else {
-            error!("Line {} - No vertex found ({})",_line_count,line_data);
+        // some benchmark files mark the end of the clauses with %
+        if line_fields[0].1.starts_with('%') {
+            break;
+        }
+        if line_fields[0].1 == "p" {
+            let header_error = |column: usize| ParseError::new(ParseErrorKind::Header, line_count, column,
+                format!("expected 'p cnf <variables> <clauses>' but found '{}'",line_data.trim()));
+            if declared_clauses.is_some() || !formula.clauses.is_empty() || !literals.is_empty() {
+                return Err(ParseError::new(ParseErrorKind::Header, line_count, line_fields[0].0,
+                    "the problem line must come before any clauses".to_string()));
+            }
+            if line_fields.len() != 4 || line_fields[1].1 != "cnf" {
+                return Err(header_error(0));
+            }
+            let num_vars = line_fields[2].1.parse::<usize>().map_err(|_| header_error(line_fields[2].0))?;
+            let num_clauses = line_fields[3].1.parse::<usize>().map_err(|_| header_error(line_fields[3].0))?;
+            info!("Problem line declares {} variables and {} clauses",num_vars,num_clauses);
+            formula.num_variables = num_vars;
+            declared_clauses = Some((num_clauses,line_count));
+            continue;
+        }
+
+        if declared_clauses.is_none() && formula.clauses.is_empty() && literals.is_empty() && !skipping {
+            formula.report(mode, ParseError::new(ParseErrorKind::Header, line_count, line_fields[0].0,
+                "clause found before the 'p cnf' problem line".to_string()))?;
+        }
+
+        for (column, text) in line_fields {
+            let value = match parse_literal(text,line_count,column) {
+                Ok(value) => value,
+                Err(error) => {
+                    formula.report(mode,error)?;
+                    // keeping the rest of the clause would make it a stronger constraint
+                    skipping = true;
+                    literals.clear();
+                    continue;
+                },
+            };
+            match Literal::new(value) {
+                // 0 ends the clause
+                Err(_) if skipping => skipping = false,
+                Err(_) => {
+                    add_dimacs_clause(&mut formula,mode,&literals,clause_start,declared_clauses.is_some())?;
+                    literals.clear();
+                },
+                Ok(_) if skipping => {},
+                Ok(literal) => {
+                    if literals.is_empty() {
+                        clause_start = (line_count,column);
+                    }
+                    literals.push((column,literal));
+                },
+            }
         }
     }
-    num_variables
+
+    // the 0 on the last clause is sometimes left off
+    if !literals.is_empty() {
+        add_dimacs_clause(&mut formula,mode,&literals,clause_start,declared_clauses.is_some())?;
+    }
+    match declared_clauses {
+        Some((num_clauses, line)) if num_clauses != formula.clauses.len() => {
+            let read = formula.clauses.len();
+            formula.report(mode, ParseError::new(ParseErrorKind::Header, line, 0,
+                format!("the problem line declares {} clauses but {} were read",num_clauses,read)))?;
+        },
+        Some(_) => {},
+        None => formula.report(mode, ParseError::new(ParseErrorKind::Header, 1, 0, "no 'p cnf' problem line found".to_string()))?,
+    }
+    Ok(formula)
 }
 
+
+#[cfg(test)]
+mod tests {
+    use crate::csrgraph::CsrGraph;
+    use crate::dirgraph::DirectedGraph;
+    use crate::parse::{read_input,parse_formula,write_clauses,InputFormat,ParseMode,ParseErrorKind};
+
+    #[test]
+    fn strict_errors() {
+        let cases = [
+            ("2\n1 2\n1 x\n", ParseErrorKind::InvalidLiteral, 3, 3),
+            ("2\n1 2\n 0 -1\n", ParseErrorKind::ZeroLiteral, 3, 2),
+            ("2\n1 2\n1  -3\n", ParseErrorKind::LiteralOutOfRange, 3, 4),
+            ("2\n1 2 1\n", ParseErrorKind::MalformedClause, 2, 0),
+            ("two\n1 2\n", ParseErrorKind::Header, 1, 1),
+        ];
+        for (contents, kind, line, column) in cases {
+            let mut graph = CsrGraph::new();
+            let error = read_input(contents.as_bytes(),InputFormat::Course,ParseMode::Strict,&mut graph).unwrap_err();
+            assert_eq!((error.kind,error.line,error.column),(kind,line,column),"{}",contents);
+        }
+
+        let mut graph = CsrGraph::new();
+        let error = read_input("p cnf 3 1\n1 -2\n 3 0\n".as_bytes(),InputFormat::Auto,ParseMode::Strict,&mut graph).unwrap_err();
+        assert_eq!((error.kind,error.line,error.column),(ParseErrorKind::TooManyLiterals,2,1));
+
+        let mut graph = CsrGraph::new();
+        let error = read_input("c two declared\np cnf 3 2\n1 -2 0\n".as_bytes(),InputFormat::Auto,ParseMode::Strict,&mut graph).unwrap_err();
+        assert_eq!((error.kind,error.line,error.column),(ParseErrorKind::Header,2,0));
+    }
+
+    #[test]
+    fn lenient_warnings() {
+        let input = "\n2\n1 2\n1 x\n3 -1\nsome text here\n-2 -3\n";
+        let mut graph = CsrGraph::new();
+        let formula = read_input(input.as_bytes(),InputFormat::Auto,ParseMode::Lenient,&mut graph).unwrap();
+        assert_eq!(formula.num_variables,3);
+        assert_eq!(formula.clauses.len(),3);
+        assert_eq!(formula.clauses[1].line,5);
+        let kinds : Vec<ParseErrorKind> = formula.warnings.iter().map(|w| w.kind).collect();
+        assert_eq!(kinds,vec!(ParseErrorKind::InvalidLiteral,ParseErrorKind::LiteralOutOfRange,
+                              ParseErrorKind::MalformedClause));
+        assert_eq!(graph.vertex_count(),6);
+
+        // the whole clause with the bad literal is dropped, not just the literal
+        let input = "p cnf 3 3\n1 x 2 0\n-1 3\n 0 -2 -3 0\n";
+        let formula = parse_formula(input.as_bytes(),InputFormat::Auto,ParseMode::Lenient).unwrap();
+        let clauses : Vec<String> = formula.clauses.iter().map(|clause| clause.to_string()).collect();
+        assert_eq!(clauses,vec!("(-1 3)","(-2 -3)"));
+        let kinds : Vec<ParseErrorKind> = formula.warnings.iter().map(|w| w.kind).collect();
+        assert_eq!(kinds,vec!(ParseErrorKind::InvalidLiteral,ParseErrorKind::Header));
+    }
+
+    #[test]
+    fn clause_provenance() {
+        let input = "# numbers of variables\n3\n1 2  # first\n-1 3\n-3 -3 #\n";
+        let mut formula = parse_formula(input.as_bytes(),InputFormat::Auto,ParseMode::Strict).unwrap();
+        assert_eq!(formula.clauses.len(),3);
+        assert_eq!(formula.clauses[0].label.as_deref(),Some("first"));
+        assert_eq!(formula.clauses[2].label,None);
+        assert_eq!(formula.clause_source(1),"line 4");
+        formula.source = Some("a.txt".to_string());
+        assert_eq!(formula.clause_source(0),"a.txt line 3 (first)");
+
+        let mut graph = DirectedGraph::new();
+        formula.build_graph(&mut graph);
+        assert!(formula.edge_clause.is_empty());
+        let mut graph = DirectedGraph::new();
+        formula.build_graph_with_provenance(&mut graph);
+        // the unit clause only needs one edge
+        assert_eq!(formula.edge_clause.len(),5);
+        let mut counts = [0; 3];
+        for clause_index in formula.edge_clause.values() {
+            counts[*clause_index] += 1;
+        }
+        assert_eq!(counts,[2,2,1]);
+
+        let mut output = Vec::<u8>::new();
+        write_clauses(&mut output,formula.num_variables,&formula.clauses).unwrap();
+        let reread = parse_formula(output.as_slice(),InputFormat::Course,ParseMode::Strict).unwrap();
+        assert_eq!(reread.clauses[0].label.as_deref(),Some("first"));
+        assert_eq!(reread.clauses.len(),3);
+    }
+}
@@ -0,0 +1,229 @@
+use log::{ info, debug };
+use std::collections::{HashMap,HashSet};
+use std::fmt;
+
+use crate::backbone::implication_chain;
+use crate::condensation::Condensation;
+use crate::literal::Literal;
+use crate::scc::SccFinder;
+use crate::searchgraph::SearchGraph;
+use crate::solver::is_satisfiable;
+use crate::tarjan::Tarjan;
+
+
+/// A question about what the formula implies
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Query {
+    /// does the first literal being true make the second one true?
+    Implies(Literal,Literal),
+    /// is the clause (first OR second) true in every solution?
+    Entails(Literal,Literal),
+}
+
+impl Query {
+
+    /// Parses a query line;  "implies <a> <b>" or "entails <a> <b>".  Blank lines and
+    /// lines starting with '#' aren't queries
+    pub fn parse(line: &str) -> Result<Option<Query>,String> {
+        let fields : Vec<&str> = line.split_whitespace().collect();
+        if fields.is_empty() || fields[0].starts_with('#') {
+            return Ok(None);
+        }
+        if fields.len() != 3 {
+            return Err(format!("expected '<implies|entails> <literal> <literal>' but found '{}'",line.trim()));
+        }
+        let literal = |text: &str| text.parse::<isize>().map_err(|_| format!("invalid literal '{}'",text)).and_then(Literal::new);
+        let (first, second) = (literal(fields[1])?, literal(fields[2])?);
+        match fields[0] {
+            "implies" => Ok(Some(Query::Implies(first,second))),
+            "entails" => Ok(Some(Query::Entails(first,second))),
+            other => Err(format!("unknown query '{}'",other)),
+        }
+    }
+
+    /// The literals used by the query
+    pub fn literals(&self) -> [Literal; 2] {
+        match self {
+            Query::Implies(first, second) | Query::Entails(first, second) => [*first, *second],
+        }
+    }
+}
+
+impl fmt::Display for Query {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Query::Implies(first, second) => write!(f, "implies {} {}", first, second),
+            Query::Entails(first, second) => write!(f, "entails {} {}", first, second),
+        }
+    }
+}
+
+
+/// Why a query holds, each with the chain of implications that shows it
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Witness {
+    /// the formula can't be satisfied, so it implies everything
+    Unsatisfiable,
+    /// a chain from the first literal to the second
+    Path(Vec<Literal>),
+    /// the first literal can never be true;  a chain from it to its negation
+    Impossible(Vec<Literal>),
+    /// the second literal is always true;  a chain from its negation to it
+    Forced(Vec<Literal>),
+}
+
+impl fmt::Display for Witness {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let chain_text = |chain: &[Literal]| chain.iter().map(|literal| literal.to_string()).collect::<Vec<String>>().join(" -> ");
+        match self {
+            Witness::Unsatisfiable => write!(f, "the formula can't be satisfied"),
+            Witness::Path(chain) => write!(f, "{}", chain_text(chain)),
+            Witness::Impossible(chain) => write!(f, "{} is never true: {}", chain[0], chain_text(chain)),
+            Witness::Forced(chain) => write!(f, "{} is always true: {}", chain[chain.len()-1], chain_text(chain)),
+        }
+    }
+}
+
+
+/// Answers implication and entailment queries about one formula.  Reachability is worked out
+/// on the SCC condensation of the implication graph, and the nodes reachable from each node
+/// are cached so that many queries can be run against the same formula
+pub struct QueryEngine<'a, G: SearchGraph> {
+    graph: &'a G,
+    k: Tarjan<'a, G>,
+    condensation: Condensation,
+    satisfiable: bool,
+    /// the condensation nodes reachable from a node (including itself)
+    reachable: HashMap<isize,HashSet<isize>>,
+}
+
+impl<'a, G: SearchGraph> QueryEngine<'a, G> {
+
+    pub fn new(graph: &'a G, num_variables: usize) -> QueryEngine<'a, G> {
+        let mut k = Tarjan::new(graph);
+        k.find_scc();
+        let satisfiable = is_satisfiable(&k,num_variables);
+        let condensation = Condensation::new(graph,&k);
+        info!("Query engine has {} SCCs, satisfiable {}",condensation.node_count(),satisfiable);
+        QueryEngine { graph, k, condensation, satisfiable, reachable: HashMap::new() }
+    }
+
+    pub fn is_satisfiable(&self) -> bool {
+        self.satisfiable
+    }
+
+    /// Whether there is a chain of implications from one literal to the other
+    pub fn reaches(&mut self, from: Literal, to: Literal) -> bool {
+        let (from_node, to_node) = match (self.condensation.node_of(from.value()), self.condensation.node_of(to.value())) {
+            (Some(from_node), Some(to_node)) => (from_node, to_node),
+            _ => return false,
+        };
+        // the nodes are in topological order, so nothing reaches an earlier node
+        if from_node > to_node {
+            return false;
+        }
+        if !self.reachable.contains_key(&from_node) {
+            let reached = self.condensation.graph().reachable_from(from_node);
+            debug!("SCC {} reaches {} SCCs",from_node,reached.len());
+            self.reachable.insert(from_node,reached);
+        }
+        self.reachable[&from_node].contains(&to_node)
+    }
+
+    fn chain(&self, from: Literal, to: Literal) -> Vec<Literal> {
+        implication_chain(self.graph,&self.k,from,to).unwrap_or_default()
+    }
+
+    /// Whether every solution with the first literal true also has the second one true.
+    /// Returns the reason it does, or None if there is a solution with first true and second false
+    pub fn implies(&mut self, first: Literal, second: Literal) -> Option<Witness> {
+        if !self.satisfiable {
+            return Some(Witness::Unsatisfiable);
+        }
+        if self.reaches(first,second) {
+            return Some(Witness::Path(self.chain(first,second)));
+        }
+        if self.reaches(first,first.negate()) {
+            return Some(Witness::Impossible(self.chain(first,first.negate())));
+        }
+        if self.reaches(second.negate(),second) {
+            return Some(Witness::Forced(self.chain(second.negate(),second)));
+        }
+        None
+    }
+
+    /// Whether the clause (first OR second) is true in every solution, which is the same
+    /// as -first implying second
+    pub fn entails(&mut self, first: Literal, second: Literal) -> Option<Witness> {
+        self.implies(first.negate(),second)
+    }
+
+    pub fn answer(&mut self, query: &Query) -> Option<Witness> {
+        match query {
+            Query::Implies(first, second) => self.implies(*first,*second),
+            Query::Entails(first, second) => self.entails(*first,*second),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::assume::{solve_with_assumptions,AssumptionResult};
+    use crate::bruteforce::{lit,seeded_formula};
+    use crate::dirgraph::DirectedGraph;
+    use crate::generator::GeneratorMode;
+    use crate::literal::{Literal,Variable};
+    use crate::parse::{parse_formula,InputFormat,ParseMode};
+    use crate::query::{Query,QueryEngine,Witness};
+
+    #[test]
+    fn queries() {
+        assert_eq!(Query::parse("implies 1 -2").unwrap(),Some(Query::Implies(lit(1),lit(-2))));
+        assert_eq!(Query::parse("  # comment").unwrap(),None);
+        assert!(Query::parse("implies 1 0").is_err());
+        assert!(Query::parse("follows 1 2").is_err());
+
+        // 1 -> 2 -> 3, and 4 is always true
+        let formula = parse_formula("4\n-1 2\n-2 3\n4 4\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let mut graph = DirectedGraph::new();
+        formula.build_graph(&mut graph);
+        let mut engine = QueryEngine::new(&graph,4);
+        assert_eq!(engine.implies(lit(1),lit(3)),Some(Witness::Path(vec!(lit(1),lit(2),lit(3)))));
+        assert_eq!(engine.implies(lit(-3),lit(-1)),Some(Witness::Path(vec!(lit(-3),lit(-2),lit(-1)))));
+        assert_eq!(engine.implies(lit(3),lit(1)),None);
+        assert_eq!(engine.implies(lit(2),lit(4)),Some(Witness::Forced(vec!(lit(-4),lit(4)))));
+        assert_eq!(engine.implies(lit(-4),lit(1)),Some(Witness::Impossible(vec!(lit(-4),lit(4)))));
+        assert_eq!(engine.entails(lit(-1),lit(3)),Some(Witness::Path(vec!(lit(1),lit(2),lit(3)))));
+        assert_eq!(engine.answer(&Query::Entails(lit(1),lit(2))),None);
+        assert_eq!(Witness::Forced(vec!(lit(-4),lit(4))).to_string(),"4 is always true: -4 -> 4");
+
+        let formula = parse_formula("1\n1 1\n-1 -1\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let mut graph = DirectedGraph::new();
+        formula.build_graph(&mut graph);
+        assert_eq!(QueryEngine::new(&graph,1).implies(lit(1),lit(-1)),Some(Witness::Unsatisfiable));
+    }
+
+    #[test]
+    fn matches_assumptions() {
+        for seed in 0..50 {
+            let num_variables = 2 + (seed % 6) as usize;
+            let formula = seeded_formula(seed,GeneratorMode::Planted,num_variables,num_variables*3/2);
+            let mut graph = DirectedGraph::new();
+            formula.build_graph(&mut graph);
+            let mut engine = QueryEngine::new(&graph,num_variables);
+            let literals : Vec<Literal> = (0..num_variables).map(Variable::from_index)
+                .flat_map(|var| [var.positive(), var.negative()]).collect();
+            for first in &literals {
+                for second in &literals {
+                    // first implies second exactly when first and -second can't both be true
+                    let expected = !matches!(solve_with_assumptions(&graph,num_variables,&[*first,second.negate()]).unwrap(),
+                                             AssumptionResult::Satisfiable(_));
+                    assert_eq!(engine.implies(*first,*second).is_some(),expected,"seed {} {} implies {}",seed,first,second);
+                }
+            }
+        }
+    }
+}
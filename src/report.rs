@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use crate::conflict::{Conflict,ImplicationStep};
+use crate::literal::Variable;
+use crate::parse::Formula;
+use crate::scc::SccFinder;
+use crate::simplify::Simplification;
+use crate::solver::find_assignment;
+
+
+/// Number of SCC sizes included in a report
+pub const LARGEST_SCC_COUNT : usize = 10;
+
+/// One implication of a conflict path, with the clause it came from
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct StepReport {
+    pub source: isize,
+    pub dest: isize,
+    pub clause: Option<String>,
+    /// where the clause was read from (e.g. "input.txt line 3")
+    pub location: Option<String>,
+}
+
+/// The variable that is in the same SCC as its negation, and the paths that show it
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct ConflictReport {
+    pub variable: Variable,
+    pub forward: Vec<StepReport>,
+    pub backward: Vec<StepReport>,
+}
+
+/// The result of solving a formula along with the input statistics, SCC statistics and
+/// phase timings, so that it can be written out in a machine readable form
+#[derive(Debug,Clone,PartialEq)]
+pub struct Report {
+    pub input: Option<String>,
+    pub num_variables: usize,
+    pub num_clauses: usize,
+    pub warnings: usize,
+    /// variables removed by simplifying the formula, if it was simplified
+    pub eliminated_variables: Option<usize>,
+    pub satisfiable: bool,
+    pub assignment: Option<Vec<bool>>,
+    pub scc_count: usize,
+    /// the largest SCC sizes, largest first
+    pub largest_scc_sizes: Vec<usize>,
+    pub conflict: Option<ConflictReport>,
+    /// false when the formula can't be satisfied but the conflict wasn't looked for
+    pub conflict_computed: bool,
+    /// time taken by each phase, in the order they ran
+    pub timings: Vec<(String,Duration)>,
+}
+
+impl Report {
+
+    /// Creates the report for a formula from the SCCs of its implication graph
+    pub fn new(formula: &Formula, k: &dyn SccFinder) -> Report {
+        let assignment = find_assignment(k,formula.num_variables);
+        let mut sizes = k.get_scc_sizes();
+        sizes.sort_by(|a, b| b.cmp(a));
+        sizes.truncate(LARGEST_SCC_COUNT);
+        Report {
+            input: formula.source.clone(),
+            num_variables: formula.num_variables,
+            num_clauses: formula.clauses.len(),
+            warnings: formula.warnings.len(),
+            eliminated_variables: None,
+            satisfiable: assignment.is_some(),
+            assignment,
+            scc_count: k.scc_count(),
+            largest_scc_sizes: sizes,
+            conflict: None,
+            conflict_computed: true,
+            timings: Vec::new(),
+        }
+    }
+
+    /// Records that the formula was simplified, extending the assignment to the eliminated variables
+    pub fn add_simplification(&mut self, simplification: &Simplification) {
+        self.eliminated_variables = Some(simplification.eliminated_count());
+        self.assignment = self.assignment.as_ref().map(|assignment| simplification.extend_assignment(assignment));
+    }
+
+    pub fn add_conflict(&mut self, conflict: &Conflict, formula: &Formula) {
+        let steps = |path: &[ImplicationStep]| path.iter().map(|step| StepReport {
+            source: step.source,
+            dest: step.dest,
+            clause: step.clause.map(|index| formula.clauses[index].to_string()),
+            location: step.clause.map(|index| formula.clause_source(index)),
+        }).collect();
+        self.conflict = Some(ConflictReport { variable: conflict.variable, forward: steps(&conflict.forward), backward: steps(&conflict.backward) });
+    }
+
+    /// Records that the conflict wasn't looked for (e.g. the graph was built without the clause of each edge)
+    pub fn skip_conflict(&mut self) {
+        self.conflict = None;
+        self.conflict_computed = false;
+    }
+
+    pub fn add_timing(&mut self, phase: &str, elapsed: Duration) {
+        self.timings.push((phase.to_string(),elapsed));
+    }
+
+    /// Formats the report as a JSON object;  the assignment is a list of signed literals and
+    /// the timings are in milliseconds
+    pub fn to_json(&self) -> String {
+        let mut fields = Vec::<String>::new();
+        fields.push(format!("\"satisfiable\": {}",self.satisfiable));
+        fields.push(format!("\"assignment\": {}",match &self.assignment {
+            Some(assignment) => json_list(assignment.iter().enumerate().map(|(index, value)| Variable::from_index(index).literal(*value).to_string())),
+            None => "null".to_string(),
+        }));
+        fields.push(format!("\"input\": {{\"name\": {}, \"variables\": {}, \"clauses\": {}, \"warnings\": {}, \"eliminated_variables\": {}}}",
+                            self.input.as_deref().map_or("null".to_string(),json_string),self.num_variables,self.num_clauses,self.warnings,
+                            self.eliminated_variables.map_or("null".to_string(),|count| count.to_string())));
+        fields.push(format!("\"scc\": {{\"count\": {}, \"largest_sizes\": {}}}",
+                            self.scc_count,json_list(self.largest_scc_sizes.iter().map(|size| size.to_string()))));
+        fields.push(format!("\"conflict\": {}",match &self.conflict {
+            Some(conflict) => format!("{{\"variable\": {}, \"forward\": {}, \"backward\": {}}}",
+                                      conflict.variable,steps_json(&conflict.forward),steps_json(&conflict.backward)),
+            None => "null".to_string(),
+        }));
+        fields.push(format!("\"conflict_computed\": {}",self.conflict_computed));
+        let timings : Vec<String> = self.timings.iter()
+            .map(|(phase, elapsed)| format!("{}: {:.3}",json_string(phase),elapsed.as_secs_f64() * 1000.0))
+            .collect();
+        fields.push(format!("\"timings_ms\": {{{}}}",timings.join(", ")));
+        format!("{{\n  {}\n}}",fields.join(",\n  "))
+    }
+}
+
+fn json_list(items: impl Iterator<Item=String>) -> String {
+    format!("[{}]",items.collect::<Vec<String>>().join(", "))
+}
+
+fn steps_json(steps: &[StepReport]) -> String {
+    let optional = |text: &Option<String>| text.as_deref().map_or("null".to_string(),json_string);
+    json_list(steps.iter().map(|step| format!("{{\"source\": {}, \"dest\": {}, \"clause\": {}, \"location\": {}}}",
+                                              step.source,step.dest,optional(&step.clause),optional(&step.location))))
+}
+
+/// Quotes a string for JSON, escaping the characters that need it
+pub fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}",c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::conflict::find_conflict;
+    use crate::dirgraph::DirectedGraph;
+    use crate::kosaraju::Kosaraju;
+    use crate::parse::{parse_formula,InputFormat,ParseMode};
+    use crate::report::{json_string,Report};
+    use crate::scc::SccFinder;
+
+    #[test]
+    fn json_report() {
+        let mut formula = parse_formula("3\n1 2\n-1 2\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        formula.source = Some("a \"quoted\" name".to_string());
+        let mut graph = DirectedGraph::new();
+        formula.build_graph_with_provenance(&mut graph);
+        let mut k = Kosaraju::new(&graph,true);
+        k.find_scc();
+        let mut report = Report::new(&formula,&k);
+        report.add_timing("scc",Duration::from_micros(1500));
+        let json = report.to_json();
+        assert!(json.contains("\"satisfiable\": true,\n"));
+        assert!(json.contains("\"assignment\": [1, 2, 3],\n"));
+        assert!(json.contains("\"name\": \"a \\\"quoted\\\" name\", \"variables\": 3, \"clauses\": 2"));
+        assert!(json.contains("\"scc\": {\"count\": 6, \"largest_sizes\": [1, 1, 1, 1, 1, 1]}"));
+        assert!(json.contains("\"conflict\": null"));
+        assert!(json.ends_with("\"timings_ms\": {\"scc\": 1.500}\n}"));
+
+        let mut formula = parse_formula("1\n1 1\n-1 -1\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let mut graph = DirectedGraph::new();
+        formula.build_graph_with_provenance(&mut graph);
+        let mut k = Kosaraju::new(&graph,true);
+        k.find_scc();
+        let mut report = Report::new(&formula,&k);
+        report.add_conflict(&find_conflict(&graph,&k,&formula).unwrap(),&formula);
+        let json = report.to_json();
+        assert!(json.contains("\"assignment\": null"));
+        assert!(json.contains("\"conflict\": {\"variable\": 1, \"forward\": [{\"source\": 1, \"dest\": -1, \"clause\": \"(-1 -1)\", \"location\": \"line 3\"}]"));
+        assert!(json.contains("\"conflict_computed\": true"));
+        report.skip_conflict();
+        assert!(report.to_json().contains("\"conflict\": null,\n  \"conflict_computed\": false,\n"));
+
+        assert_eq!(json_string("tab\there\u{1}"),"\"tab\\there\\u0001\"");
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::io::Write;
+use clap::ValueEnum;
+
+use crate::log_files::LogFile;
+
+
+/// The strongly connected component algorithms that can be selected
+#[derive(Debug,Clone,Copy,PartialEq,Eq,ValueEnum)]
+pub enum SccAlgorithm {
+    Kosaraju,
+    Tarjan,
+    Gabow,
+}
+
+/// Common interface for the algorithms that find the strongly connected components of a graph.
+///
+/// Each SCC is identified by one of its vertexes (the first one the algorithm reached), and
+/// the SCCs are ranked in the order they are found, which for all of the algorithms is reverse
+/// topological order (i.e. an SCC is found before any SCC that has an edge into it)
+pub trait SccFinder {
+
+    /// Finds all of the strongly connected components in the graph
+    fn find_scc(&mut self);
+
+    /// Returns the id of the SCC containing the vertex
+    fn get_group(&self, vertex_id: isize) -> Option<&isize>;
+
+    /// Returns the position of the vertex's SCC in the order the SCCs were found
+    fn get_scc_rank(&self, vertex_id: isize) -> Option<usize>;
+
+    /// Returns the size of each SCC (in no particular order)
+    fn get_scc_sizes(&self) -> Vec<usize>;
+
+    /// Returns the list of vertexes in an SCC
+    fn get_scc_group(&self, group: isize) -> Vec<isize>;
+
+    /// Returns the total number of SCCs found
+    fn scc_count(&self) -> usize {
+        self.get_scc_sizes().len()
+    }
+
+    /// Writes a summary of the SCCs and a file per SCC with more than one vertex
+    fn log_scc_to_files(&self, prefix: &str);
+}
+
+
+/// Writes the SCCs to log files;  a summary file with the size of each SCC that has more than one
+/// vertex, a file listing each of those SCCs, and one file listing all the single vertex SCCs
+pub fn log_scc_groups(groups: &HashMap<isize,Vec<isize>>, prefix: &str) {
+
+    let summary_file = LogFile::new(&format!("{}_scc_summary.txt",prefix)[..]).unwrap();
+    let mut sort_by_count: Vec<(&isize, &Vec<isize>)> = groups.iter().collect();
+    sort_by_count.sort_by_key(|(_, members)| std::cmp::Reverse(members.len()));
+
+    let single_scc_file = LogFile::new(&format!("{}/1_single_vertex.scc",prefix)[..]).unwrap();
+    for (group_id, vertex_list) in sort_by_count {
+        let length = vertex_list.len();
+        if length > 1 {
+            log_writeln!(summary_file,"{}  group {}",length,group_id);
+            let scc_file = LogFile::new(&format!("{}/{}_{}.scc",prefix,length,group_id)[..]).unwrap();
+            let mut sorted_list : Vec<&isize> = vertex_list.iter().collect();
+            sorted_list.sort();
+            for v in sorted_list {
+                log_writeln!(scc_file,"{}",v);
+            }
+        }
+        else {
+            log_writeln!(single_scc_file,"{}",group_id);
+        }
+    }
+}
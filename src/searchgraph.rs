@@ -0,0 +1,155 @@
+use std::collections::{HashMap,HashSet};
+
+/// Read only view of a graph used by the SCC algorithms, so that they can run on
+/// any of the graph representations
+pub trait SearchGraph {
+
+    /// get a complete list of vertex ids in the graph
+    fn get_vertex_ids(&self) -> Vec<isize>;
+
+    /// retreives a vector of the vertex ids that a vertex has outgoing edges to
+    fn get_outgoing_vertex_ids(&self, vertex: isize) -> Vec<isize>;
+
+    /// retreives a vector of the vertex ids that have edges incoming to a vertex
+    fn get_incoming_vertex_ids(&self, vertex: isize) -> Vec<isize>;
+
+    /// the number of dense vertex indexes, for graphs whose vertexes map onto 0..n
+    fn dense_vertex_count(&self) -> Option<usize> {
+        None
+    }
+
+    /// the dense index of a vertex (see dense_vertex_count)
+    fn dense_index(&self, _vertex: isize) -> Option<usize> {
+        None
+    }
+
+    /// get the set of vertexes that can be reached from a vertex (including itself)
+    fn reachable_from(&self, vertex: isize) -> HashSet<isize> {
+        let mut reached = HashSet::<isize>::new();
+        let mut stack = vec!(vertex);
+        reached.insert(vertex);
+        while let Some(current) = stack.pop() {
+            for dest in self.get_outgoing_vertex_ids(current) {
+                if reached.insert(dest) {
+                    stack.push(dest);
+                }
+            }
+        }
+        reached
+    }
+}
+
+
+/// Per vertex state for the graph algorithms.  It's kept in a vector when the graph has dense
+/// vertex indexes, and in a map otherwise (or for a vertex without a dense index)
+#[derive(Debug,Clone)]
+pub struct VertexMap<'a, G: SearchGraph, T> {
+    graph: &'a G,
+    dense: Vec<Option<T>>,
+    sparse: HashMap<isize,T>,
+    len: usize,
+}
+
+impl<'a, G: SearchGraph, T> VertexMap<'a, G, T> {
+
+    pub fn new(graph: &'a G) -> VertexMap<'a, G, T> {
+        let mut dense = Vec::<Option<T>>::new();
+        dense.resize_with(graph.dense_vertex_count().unwrap_or(0),|| None);
+        VertexMap { graph, dense, sparse: HashMap::new(), len: 0 }
+    }
+
+    fn slot(&self, vertex: isize) -> Option<usize> {
+        self.graph.dense_index(vertex).filter(|index| *index < self.dense.len())
+    }
+
+    pub fn get(&self, vertex: isize) -> Option<&T> {
+        match self.slot(vertex) {
+            Some(index) => self.dense[index].as_ref(),
+            None => self.sparse.get(&vertex),
+        }
+    }
+
+    pub fn get_mut(&mut self, vertex: isize) -> Option<&mut T> {
+        match self.slot(vertex) {
+            Some(index) => self.dense[index].as_mut(),
+            None => self.sparse.get_mut(&vertex),
+        }
+    }
+
+    pub fn contains_key(&self, vertex: isize) -> bool {
+        self.get(vertex).is_some()
+    }
+
+    /// Sets the vertex's value, returning the value it replaced
+    pub fn insert(&mut self, vertex: isize, value: T) -> Option<T> {
+        let old = match self.slot(vertex) {
+            Some(index) => self.dense[index].replace(value),
+            None => self.sparse.insert(vertex,value),
+        };
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    pub fn remove(&mut self, vertex: isize) -> Option<T> {
+        let old = match self.slot(vertex) {
+            Some(index) => self.dense[index].take(),
+            None => self.sparse.remove(&vertex),
+        };
+        if old.is_some() {
+            self.len -= 1;
+        }
+        old
+    }
+
+    /// The number of vertexes with a value
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes every value, keeping the space for them
+    pub fn clear(&mut self) {
+        self.dense.iter_mut().for_each(|value| *value = None);
+        self.sparse.clear();
+        self.len = 0;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::csrgraph::CsrGraph;
+    use crate::dirgraph::DirectedGraph;
+    use crate::graphbuilder::GraphBuilder;
+    use crate::searchgraph::VertexMap;
+
+    #[test]
+    fn dense_and_sparse_maps() {
+        let mut csr = CsrGraph::new();
+        (&mut csr).add_edge(1,-2,1);
+        (&mut csr).finish();
+        let directed = DirectedGraph::new();
+
+        let mut dense = VertexMap::<CsrGraph,usize>::new(&csr);
+        let mut sparse = VertexMap::<DirectedGraph,usize>::new(&directed);
+        for (vertex, value) in [(1,10),(-2,20),(7,70),(0,0)] {
+            assert_eq!(dense.insert(vertex,value),None);
+            assert_eq!(sparse.insert(vertex,value),None);
+        }
+        assert_eq!(dense.insert(-2,21),Some(20));
+        assert_eq!(dense.len(),4);
+        assert_eq!(dense.get(-2),Some(&21));
+        assert_eq!(dense.get(7),Some(&70));
+        assert_eq!(dense.get(2),None);
+        *sparse.get_mut(7).unwrap() += 1;
+        assert_eq!(sparse.remove(7),Some(71));
+        assert_eq!(sparse.len(),3);
+        dense.clear();
+        assert!(dense.is_empty() && !dense.contains_key(1));
+    }
+}
@@ -0,0 +1,417 @@
+use log::{ info, debug };
+use std::collections::{HashMap,HashSet};
+
+use crate::csrgraph::CsrGraph;
+use crate::literal::{Literal,Variable};
+use crate::parse::{Clause,Formula};
+use crate::scc::SccFinder;
+use crate::tarjan::Tarjan;
+
+
+/// How many clauses and variables a simplification rule removed
+#[derive(Debug,Clone,Copy,Default,PartialEq,Eq)]
+pub struct RuleStats {
+    pub clauses: usize,
+    pub variables: usize,
+}
+
+/// What each of the simplification rules removed from the formula
+#[derive(Debug,Clone,Default,PartialEq,Eq)]
+pub struct SimplifyStats {
+    /// clauses that are the same as an earlier clause (either way round)
+    pub duplicates: RuleStats,
+    /// clauses (x OR -x) that are always true
+    pub tautologies: RuleStats,
+    /// variables fixed by unit clauses and the clauses that were satisfied or shortened by them
+    pub units: RuleStats,
+    /// variables that only appear with one sign and the clauses they satisfy
+    pub pure_literals: RuleStats,
+    /// variables replaced by an equivalent literal from the same SCC and the clauses that became redundant
+    pub equivalences: RuleStats,
+    /// number of times the rules were applied before nothing more changed
+    pub rounds: usize,
+}
+
+impl SimplifyStats {
+
+    /// One line per rule, for reporting
+    pub fn report(&self) -> Vec<String> {
+        let rules = [
+            ("duplicates", self.duplicates),
+            ("tautologies", self.tautologies),
+            ("unit clauses", self.units),
+            ("pure literals", self.pure_literals),
+            ("equivalences", self.equivalences),
+        ];
+        rules.iter().map(|(name, stats)|
+            format!("{:<14} removed {} clauses and {} variables",name,stats.clauses,stats.variables)).collect()
+    }
+}
+
+/// How a variable was removed from the formula
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+enum Elimination {
+    /// the variable has to have (or can be given) this value
+    Fixed(bool),
+    /// the variable always has the same value as the literal
+    Equivalent(Literal),
+}
+
+/// The result of simplifying a formula;  what was removed and what is needed to turn an
+/// assignment for the simplified formula into one for the original formula
+#[derive(Debug,Clone,Default)]
+pub struct Simplification {
+    pub stats: SimplifyStats,
+    /// the unit clauses contradict each other, so the formula can't be satisfied
+    pub unsatisfiable: bool,
+    num_variables: usize,
+    /// the removed variables in the order they were removed
+    eliminated: Vec<(Variable,Elimination)>,
+}
+
+impl Simplification {
+
+    /// Number of variables that no longer appear in the formula
+    pub fn eliminated_count(&self) -> usize {
+        self.eliminated.len()
+    }
+
+    /// Fills in the values of the removed variables, turning an assignment for the simplified
+    /// formula into one for the original formula.  Variables are filled in the reverse of the
+    /// order they were removed so an equivalent literal always has its value already
+    pub fn extend_assignment(&self, assignment: &[bool]) -> Vec<bool> {
+        let mut result = assignment.to_vec();
+        result.resize(self.num_variables.max(assignment.len()),false);
+        for (var, elimination) in self.eliminated.iter().rev() {
+            result[var.index()] = match elimination {
+                Elimination::Fixed(value) => *value,
+                Elimination::Equivalent(literal) => literal.is_true(&result).unwrap_or(false),
+            };
+        }
+        result
+    }
+}
+
+
+/// Applies the simplification rules to a formula's clauses until none of them change anything.
+///
+/// The variables keep their numbers, so the simplified formula has the same number of variables
+/// with the removed ones no longer used by any clause.  Clauses that are kept (or rewritten)
+/// keep their line and label.  If the unit clauses contradict each other the formula is
+/// replaced by the two contradicting unit clauses
+pub fn simplify(formula: &mut Formula) -> Simplification {
+
+    let mut simplifier = Simplifier {
+        values: vec![None; formula.num_variables],
+        clauses: std::mem::take(&mut formula.clauses),
+        result: Simplification { num_variables: formula.num_variables, ..Simplification::default() },
+    };
+    let start_count = simplifier.clauses.len();
+
+    loop {
+        simplifier.result.stats.rounds += 1;
+        let (tautologies, duplicates) = simplifier.remove_redundant();
+        simplifier.result.stats.tautologies.clauses += tautologies;
+        simplifier.result.stats.duplicates.clauses += duplicates;
+        let mut changed = tautologies + duplicates > 0;
+        match simplifier.propagate_units() {
+            Ok(units_changed) => changed |= units_changed,
+            Err(var) => {
+                info!("Unit clauses force variable {} to be both true and false",var);
+                simplifier.clauses = vec!(Clause::new(var.positive(),var.positive()), Clause::new(var.negative(),var.negative()));
+                simplifier.result.unsatisfiable = true;
+                break;
+            },
+        }
+        changed |= simplifier.eliminate_pure_literals();
+        changed |= simplifier.collapse_equivalences();
+        if !changed {
+            break;
+        }
+    }
+
+    formula.clauses = simplifier.clauses;
+    formula.edge_clause.clear();
+    info!("Simplified {} clauses to {} in {} rounds, removing {} variables",
+          start_count,formula.clauses.len(),simplifier.result.stats.rounds,simplifier.result.eliminated.len());
+    simplifier.result
+}
+
+
+struct Simplifier {
+    clauses: Vec<Clause>,
+    /// values of the variables fixed so far
+    values: Vec<Option<bool>>,
+    result: Simplification,
+}
+
+impl Simplifier {
+
+    fn eliminate(&mut self, var: Variable, elimination: Elimination) {
+        debug!("Eliminated variable {}: {:?}",var,elimination);
+        if let Elimination::Fixed(value) = elimination {
+            self.values[var.index()] = Some(value);
+        }
+        self.result.eliminated.push((var,elimination));
+    }
+
+    /// Removes tautologies and duplicate clauses, returning how many of each were removed
+    fn remove_redundant(&mut self) -> (usize,usize) {
+        let mut seen = HashSet::<(Literal,Literal)>::new();
+        let (mut tautologies, mut duplicates) = (0, 0);
+        self.clauses.retain(|clause| {
+            if clause.first == clause.second.negate() {
+                tautologies += 1;
+                return false;
+            }
+            let key = (clause.first.min(clause.second), clause.first.max(clause.second));
+            if !seen.insert(key) {
+                duplicates += 1;
+                return false;
+            }
+            true
+        });
+        (tautologies,duplicates)
+    }
+
+    /// Clause indexes for each literal (by its dense index)
+    fn occurrences(&self) -> Vec<Vec<usize>> {
+        let mut occurrences = vec![Vec::<usize>::new(); self.values.len() * 2];
+        for (clause_index, clause) in self.clauses.iter().enumerate() {
+            occurrences[clause.first.index()].push(clause_index);
+            if clause.second != clause.first {
+                occurrences[clause.second.index()].push(clause_index);
+            }
+        }
+        occurrences
+    }
+
+    /// Removes the clauses marked as removed, returning how many there were
+    fn drop_removed(&mut self, removed: &[bool]) -> usize {
+        let before = self.clauses.len();
+        let mut index = 0;
+        self.clauses.retain(|_| { index += 1; !removed[index-1] });
+        before - self.clauses.len()
+    }
+
+    /// Sets the literal of each unit clause (l OR l) to true, which removes the clauses
+    /// containing l and turns each clause (-l OR x) into the unit clause x.  Returns the
+    /// variable that has to be both true and false if the unit clauses contradict each other
+    fn propagate_units(&mut self) -> Result<bool,Variable> {
+        let occurrences = self.occurrences();
+        let mut removed = vec![false; self.clauses.len()];
+        let mut queue : Vec<Literal> = self.clauses.iter().filter(|clause| clause.first == clause.second).map(|clause| clause.first).collect();
+        let mut fixed = 0;
+
+        while let Some(literal) = queue.pop() {
+            let var = literal.var();
+            match self.values[var.index()] {
+                Some(value) if value == literal.is_positive() => continue,
+                Some(_) => return Err(var),
+                None => {},
+            }
+            self.eliminate(var,Elimination::Fixed(literal.is_positive()));
+            fixed += 1;
+            for clause_index in &occurrences[literal.index()] {
+                removed[*clause_index] = true;
+            }
+            for clause_index in &occurrences[literal.negate().index()] {
+                if !removed[*clause_index] {
+                    removed[*clause_index] = true;
+                    let clause = &self.clauses[*clause_index];
+                    let other = if clause.first == literal.negate() { clause.second } else { clause.first };
+                    queue.push(other);
+                }
+            }
+        }
+
+        let dropped = self.drop_removed(&removed);
+        let stats = &mut self.result.stats.units;
+        stats.variables += fixed;
+        stats.clauses += dropped;
+        Ok(fixed > 0)
+    }
+
+    /// Sets each literal whose negation isn't used by any clause to true, which removes
+    /// all of the clauses it's in (and can make more literals pure)
+    fn eliminate_pure_literals(&mut self) -> bool {
+        let occurrences = self.occurrences();
+        let mut counts : Vec<usize> = occurrences.iter().map(|clauses| clauses.len()).collect();
+        let mut removed = vec![false; self.clauses.len()];
+        let mut queue : Vec<Literal> = (0..counts.len()).map(Literal::from_index)
+            .filter(|literal| counts[literal.index()] > 0 && counts[literal.negate().index()] == 0).collect();
+        let mut fixed = 0;
+
+        while let Some(literal) = queue.pop() {
+            let var = literal.var();
+            if self.values[var.index()].is_some() || counts[literal.index()] == 0 {
+                continue;
+            }
+            self.eliminate(var,Elimination::Fixed(literal.is_positive()));
+            fixed += 1;
+            for clause_index in &occurrences[literal.index()] {
+                if removed[*clause_index] {
+                    continue;
+                }
+                removed[*clause_index] = true;
+                let clause = &self.clauses[*clause_index];
+                let other = if clause.first == literal { clause.second } else { clause.first };
+                if other != literal {
+                    counts[other.index()] -= 1;
+                    // the other literal is gone, so its negation may now be pure
+                    if counts[other.index()] == 0 && counts[other.negate().index()] > 0 {
+                        queue.push(other.negate());
+                    }
+                }
+            }
+            counts[literal.index()] = 0;
+        }
+
+        let dropped = self.drop_removed(&removed);
+        let stats = &mut self.result.stats.pure_literals;
+        stats.variables += fixed;
+        stats.clauses += dropped;
+        fixed > 0
+    }
+
+    /// Finds the SCCs of the implication graph;  all of the literals in an SCC have the same
+    /// value, so each variable is replaced by the literal with the lowest variable number in
+    /// its SCC.  Stops without changing anything if a variable and its negation are in the
+    /// same SCC, as the formula can't be satisfied
+    fn collapse_equivalences(&mut self) -> bool {
+        let num_variables = self.values.len();
+        let mut graph_formula = Formula::new(num_variables);
+        graph_formula.clauses = self.clauses.clone();
+        let mut graph = CsrGraph::new();
+        graph_formula.build_graph(&mut graph);
+        let mut k = Tarjan::new(&graph);
+        k.find_scc();
+
+        // the literal that stands for each SCC
+        let mut representative = HashMap::<isize,Literal>::new();
+        let mut substitution = vec![None; num_variables];
+        for (index, substitute) in substitution.iter_mut().enumerate() {
+            let var = Variable::from_index(index);
+            let (positive, negative) = (var.positive(), var.negative());
+            let (group, not_group) = match (k.get_group(positive.value()), k.get_group(negative.value())) {
+                (Some(group), Some(not_group)) => (*group, *not_group),
+                _ => continue,
+            };
+            if group == not_group {
+                debug!("Variable {} is equivalent to its negation",var);
+                return false;
+            }
+            match representative.get(&group) {
+                Some(literal) => *substitute = Some(*literal),
+                None => {
+                    representative.insert(group,positive);
+                    representative.insert(not_group,negative);
+                },
+            }
+        }
+
+        let mut substituted = 0;
+        for (index, literal) in substitution.iter().enumerate() {
+            if let Some(literal) = literal {
+                self.eliminate(Variable::from_index(index),Elimination::Equivalent(*literal));
+                substituted += 1;
+            }
+        }
+        if substituted == 0 {
+            return false;
+        }
+
+        let replace = |literal: Literal| match substitution[literal.var().index()] {
+            Some(equivalent) if literal.is_positive() => equivalent,
+            Some(equivalent) => equivalent.negate(),
+            None => literal,
+        };
+        for clause in self.clauses.iter_mut() {
+            clause.first = replace(clause.first);
+            clause.second = replace(clause.second);
+        }
+        // clauses that became duplicates or tautologies are counted as removed by the substitution
+        let (tautologies, duplicates) = self.remove_redundant();
+        self.result.stats.equivalences.clauses += tautologies + duplicates;
+        self.result.stats.equivalences.variables += substituted;
+        true
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng,SeedableRng};
+    use crate::bruteforce::{brute_force,lit,satisfies,seeded_formula};
+    use crate::generator::GeneratorMode;
+    use crate::parse::{parse_formula,Formula,InputFormat,ParseMode};
+    use crate::simplify::{simplify,RuleStats};
+    use crate::solver::{Solver,Solution};
+
+    #[test]
+    fn simplify_rules() {
+        let input = "6\n1 2\n2 1\n3 -3\n4 4\n-4 5\n-5 6\n-6 -1\n-1 2\n2 3\n";
+        let mut formula = parse_formula(input.as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let original = formula.clone();
+        let simplification = simplify(&mut formula);
+        let stats = &simplification.stats;
+        assert_eq!(stats.duplicates,RuleStats { clauses: 1, variables: 0 });
+        assert_eq!(stats.tautologies,RuleStats { clauses: 1, variables: 0 });
+        // 4 -> 5 -> 6 -> -1, then -1 forces 2
+        assert_eq!(stats.units,RuleStats { clauses: 7, variables: 5 });
+        assert!(!simplification.unsatisfiable);
+        assert!(formula.clauses.is_empty());
+        assert_eq!(simplification.eliminated_count(),5);
+        let assignment = simplification.extend_assignment(&[false; 6]);
+        assert!(satisfies(&assignment,&original.clauses));
+
+        // 1 is pure, and removing its clauses makes 3 pure
+        let mut formula = parse_formula("3\n1 2\n1 -3\n2 3\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let simplification = simplify(&mut formula);
+        assert_eq!(simplification.stats.pure_literals,RuleStats { clauses: 3, variables: 2 });
+        assert!(formula.clauses.is_empty());
+
+        let mut formula = parse_formula("3\n1 1\n-1 2\n-2 -1\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let simplification = simplify(&mut formula);
+        assert!(simplification.unsatisfiable);
+        assert_eq!(Solver::new().solve(&formula),Solution::Unsatisfiable);
+
+        // 1 -> 2 -> 3 -> -4 -> 1 is a cycle, so every clause becomes a tautology
+        let mut formula = Formula::new(4);
+        for (first, second) in [(-1,2),(-2,3),(-3,1),(4,1),(-4,-3),(3,4)] {
+            formula.add_clause(lit(first),lit(second));
+        }
+        let original = formula.clone();
+        let simplification = simplify(&mut formula);
+        assert_eq!(simplification.stats.equivalences,RuleStats { clauses: 6, variables: 3 });
+        assert!(formula.clauses.is_empty());
+        let solution = Solver::new().solve(&formula);
+        let assignment = simplification.extend_assignment(solution.assignment().unwrap());
+        assert!(satisfies(&assignment,&original.clauses));
+    }
+
+    #[test]
+    fn matches_original() {
+        for seed in 0..300 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let num_variables = 1 + (seed % 10) as usize;
+            let num_clauses = rng.gen_range(0..=2*num_variables);
+            let mode = if seed % 3 == 0 { GeneratorMode::Planted } else { GeneratorMode::Random };
+            let mut formula = seeded_formula(seed,mode,num_variables,num_clauses);
+            let original = formula.clone();
+            let expected = brute_force(num_variables,&original.clauses).is_some();
+
+            let simplification = simplify(&mut formula);
+            assert!(formula.clauses.len() <= original.clauses.len());
+            match Solver::new().solve(&formula) {
+                Solution::Satisfiable(assignment) => {
+                    assert!(expected,"seed {} simplified formula is satisfiable",seed);
+                    let assignment = simplification.extend_assignment(&assignment);
+                    assert!(satisfies(&assignment,&original.clauses),"seed {} assignment {:?}",seed,assignment);
+                },
+                Solution::Unsatisfiable => assert!(!expected,"seed {} simplified formula is unsatisfiable",seed),
+            }
+        }
+    }
+}
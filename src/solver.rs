@@ -0,0 +1,163 @@
+use log::{ info, debug };
+use crate::csrgraph::CsrGraph;
+use crate::gabow::Gabow;
+use crate::kosaraju::Kosaraju;
+use crate::literal::{Literal,Variable};
+use crate::parse::Formula;
+use crate::scc::{SccAlgorithm,SccFinder};
+use crate::tarjan::Tarjan;
+
+
+/// Checks whether the constraints can be met, i.e. that no variable ends up
+/// in the same SCC as its negation
+pub fn is_satisfiable(k: &dyn SccFinder, num_variables: usize) -> bool {
+
+    for i in 0..num_variables {
+        let var = Variable::from_index(i);
+        if k.get_group(var.positive().value()) == k.get_group(var.negative().value()) {
+            debug!("Variable {} and its negation are in the same SCC",var);
+            return false;
+        }
+    }
+    true
+}
+
+/// Builds a satisfying assignment from the SCC results, or None if the constraints
+/// can't be met.  Entry i of the result is the value of variable i+1.
+///
+/// A variable is set to true when its SCC comes after the SCC of its negation in
+/// topological order, which means that x can never imply -x.
+pub fn find_assignment(k: &dyn SccFinder, num_variables: usize) -> Option<Vec<bool>> {
+
+    if !is_satisfiable(k, num_variables) {
+        info!("Constraints can not be met - no assignment");
+        return None;
+    }
+
+    let mut assignment = Vec::<bool>::with_capacity(num_variables);
+    for i in 0..num_variables {
+        let var = Variable::from_index(i);
+        // SCCs are ranked in reverse topological order, so the lower rank is the later one
+        let value = match (k.get_scc_rank(var.positive().value()), k.get_scc_rank(var.negative().value())) {
+            (Some(rank), Some(not_rank)) => rank < not_rank,
+            _ => false,
+        };
+        assignment.push(value);
+    }
+    Some(assignment)
+}
+
+/// Formats an assignment as a single 'v' line with variables listed as signed
+/// literals and terminated by 0  (e.g. "v 1 -2 3 0")
+pub fn format_assignment(assignment: &[bool]) -> String {
+
+    let mut line = String::from("v");
+    for (index, value) in assignment.iter().enumerate() {
+        let literal = Variable::from_index(index).literal(*value);
+        line.push_str(&format!(" {}",literal));
+    }
+    line.push_str(" 0");
+    line
+}
+
+
+/// The result of solving a formula
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Solution {
+    /// the formula can be satisfied;  entry i is the value of variable i+1
+    Satisfiable(Vec<bool>),
+    Unsatisfiable,
+}
+
+impl Solution {
+
+    pub fn is_satisfiable(&self) -> bool {
+        matches!(self, Solution::Satisfiable(_))
+    }
+
+    /// Returns the satisfying assignment (entry i is the value of variable i+1), if there is one
+    pub fn assignment(&self) -> Option<&[bool]> {
+        match self {
+            Solution::Satisfiable(assignment) => Some(assignment),
+            Solution::Unsatisfiable => None,
+        }
+    }
+
+    /// Returns whether the literal is true in the satisfying assignment, if there is one
+    pub fn value(&self, literal: Literal) -> Option<bool> {
+        literal.is_true(self.assignment()?)
+    }
+}
+
+
+/// Solves 2-SAT formulas by building the implication graph and finding its SCCs.
+///
+/// Uses Tarjan's algorithm by default;  a different algorithm can be picked with
+/// `Solver::new().algorithm(SccAlgorithm::Gabow)`
+#[derive(Debug,Clone,Copy)]
+pub struct Solver {
+    algorithm: SccAlgorithm,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Solver::new()
+    }
+}
+
+impl Solver {
+
+    pub fn new() -> Solver {
+        Solver { algorithm: SccAlgorithm::Tarjan }
+    }
+
+    /// Sets the SCC algorithm used (Kosaraju uses its iterative search)
+    pub fn algorithm(mut self, algorithm: SccAlgorithm) -> Solver {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Builds the implication graph for the formula and finds a satisfying assignment
+    pub fn solve(&self, formula: &Formula) -> Solution {
+
+        let mut graph = CsrGraph::new();
+        formula.build_graph(&mut graph);
+
+        let mut k : Box<dyn SccFinder> = match self.algorithm {
+            SccAlgorithm::Kosaraju => Box::new(Kosaraju::new(&graph,true)),
+            SccAlgorithm::Tarjan => Box::new(Tarjan::new(&graph)),
+            SccAlgorithm::Gabow => Box::new(Gabow::new(&graph)),
+        };
+        k.find_scc();
+        match find_assignment(k.as_ref(),formula.num_variables) {
+            Some(assignment) => Solution::Satisfiable(assignment),
+            None => Solution::Unsatisfiable,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::literal::Literal;
+    use crate::parse::Formula;
+    use crate::scc::SccAlgorithm;
+    use crate::solver::{Solver,Solution};
+
+    #[test]
+    fn solve_formula() {
+        let lit = |value| Literal::new(value).unwrap();
+        let mut formula = Formula::new(3);
+        formula.add_clause(lit(1),lit(2));
+        formula.add_clause(lit(-1),lit(2));
+        formula.add_clause(lit(-2),lit(-3));
+        for algorithm in [SccAlgorithm::Kosaraju, SccAlgorithm::Tarjan, SccAlgorithm::Gabow] {
+            let solution = Solver::new().algorithm(algorithm).solve(&formula);
+            assert_eq!(solution.value(lit(2)),Some(true));
+            assert_eq!(solution.value(lit(-3)),Some(true));
+            assert_eq!(solution.value(lit(4)),None);
+        }
+        formula.add_clause(lit(-2),lit(-2));
+        assert_eq!(Solver::new().solve(&formula),Solution::Unsatisfiable);
+    }
+}
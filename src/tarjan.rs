@@ -0,0 +1,145 @@
+use log::{ info, debug, trace };
+use std::collections::HashMap;
+use crate::dirgraph::DirectedGraph;
+use crate::searchgraph::{SearchGraph,VertexMap};
+use crate::scc::{SccFinder,log_scc_groups};
+
+
+/// Tarjan's single pass SCC algorithm.
+///
+/// The depth first search is done with an explicit stack (rather than recursion) so it
+/// can be used on very large graphs without needing a large stack.
+#[derive(Debug,Clone)]
+pub struct Tarjan<'a, G: SearchGraph = DirectedGraph> {
+    graph:  &'a G,
+    /// order in which each vertex was first reached
+    index:  VertexMap<'a, G, usize>,
+    /// smallest index reachable from the vertex through vertexes still on the stack
+    lowlink:  VertexMap<'a, G, usize>,
+    stack:  Vec<isize>,
+    on_stack:  VertexMap<'a, G, ()>,
+    vertex_scc_map: VertexMap<'a, G, isize>,
+    scc_members:  HashMap<isize,Vec<isize>>,
+    scc_rank:  VertexMap<'a, G, usize>,
+}
+
+
+impl<'a, G: SearchGraph> Tarjan<'a, G> {
+
+    pub fn new(graph: &'a G) -> Tarjan<'a, G> {
+        Tarjan {
+            graph,
+            index:  VertexMap::new(graph),
+            lowlink:  VertexMap::new(graph),
+            stack:  Vec::<isize>::new(),
+            on_stack:  VertexMap::new(graph),
+            vertex_scc_map:  VertexMap::new(graph),
+            scc_members:  HashMap::<isize,Vec<isize>>::new(),
+            scc_rank:  VertexMap::new(graph),
+        }
+    }
+
+    fn visit(&mut self, vertex_id: isize) {
+        let index = self.index.len();
+        trace!("Tarjan visiting {} index {}",vertex_id,index);
+        self.index.insert(vertex_id,index);
+        self.lowlink.insert(vertex_id,index);
+        self.stack.push(vertex_id);
+        self.on_stack.insert(vertex_id,());
+    }
+
+    fn lower_lowlink(&mut self, vertex_id: isize, value: usize) {
+        let lowlink = self.lowlink.get_mut(vertex_id).unwrap();
+        if value < *lowlink {
+            *lowlink = value;
+        }
+    }
+
+    /// Pops the SCC rooted at vertex_id off the stack
+    fn pop_scc(&mut self, vertex_id: isize) {
+        let mut members = Vec::<isize>::new();
+        loop {
+            let member = self.stack.pop().unwrap();
+            self.on_stack.remove(member);
+            self.vertex_scc_map.insert(member,vertex_id);
+            members.push(member);
+            if member == vertex_id {
+                break;
+            }
+        }
+        debug!("Tarjan SCC {} has {} vertexes",vertex_id,members.len());
+        let rank = self.scc_rank.len();
+        self.scc_rank.insert(vertex_id,rank);
+        self.scc_members.insert(vertex_id,members);
+    }
+
+    /// Performs the depth first search starting at a vertex that hasn't been reached yet
+    fn strong_connect(&mut self, start_vertex: isize) {
+
+        // each entry is a vertex being searched, its outgoing vertexes and the next one to check
+        let mut call_stack = Vec::<(isize, Vec<isize>, usize)>::new();
+        self.visit(start_vertex);
+        call_stack.push((start_vertex, self.graph.get_outgoing_vertex_ids(start_vertex), 0));
+
+        while let Some((vertex_id, outgoing, next)) = call_stack.last_mut() {
+            let vertex_id = *vertex_id;
+            if *next < outgoing.len() {
+                let dest = outgoing[*next];
+                *next += 1;
+                if !self.index.contains_key(dest) {
+                    self.visit(dest);
+                    call_stack.push((dest, self.graph.get_outgoing_vertex_ids(dest), 0));
+                }
+                else if self.on_stack.contains_key(dest) {
+                    let dest_index = self.index.get(dest).cloned().unwrap();
+                    self.lower_lowlink(vertex_id,dest_index);
+                }
+            }
+            else {
+                // all of the outgoing edges have been searched
+                call_stack.pop();
+                let lowlink = self.lowlink.get(vertex_id).cloned().unwrap();
+                if let Some((parent, _, _)) = call_stack.last() {
+                    let parent = *parent;
+                    self.lower_lowlink(parent,lowlink);
+                }
+                if self.index.get(vertex_id) == Some(&lowlink) {
+                    self.pop_scc(vertex_id);
+                }
+            }
+        }
+    }
+}
+
+
+impl<'a, G: SearchGraph> SccFinder for Tarjan<'a, G> {
+
+    fn find_scc(&mut self) {
+        for vertex_id in self.graph.get_vertex_ids() {
+            if !self.index.contains_key(vertex_id) {
+                self.strong_connect(vertex_id);
+            }
+        }
+        info!("Tarjan found {} SCCs",self.scc_members.len());
+    }
+
+    fn get_group(&self, vertex_id: isize) -> Option<&isize> {
+        self.vertex_scc_map.get(vertex_id)
+    }
+
+    fn get_scc_rank(&self, vertex_id: isize) -> Option<usize> {
+        self.get_group(vertex_id).and_then(|group| self.scc_rank.get(*group).cloned())
+    }
+
+    fn get_scc_sizes(&self) -> Vec<usize> {
+        self.scc_members.values().map(|members| members.len()).collect()
+    }
+
+    fn get_scc_group(&self, group: isize) -> Vec<isize> {
+        self.scc_members.get(&group).cloned().unwrap_or_default()
+    }
+
+    fn log_scc_to_files(&self, prefix: &str) {
+        log_scc_groups(&self.scc_members,prefix);
+    }
+}
@@ -0,0 +1,135 @@
+use log::{ info, debug };
+use std::collections::BTreeSet;
+use crate::dirgraph::DirectedGraph;
+use crate::graphbuilder::GraphBuilder;
+use crate::scc::SccFinder;
+use crate::tarjan::Tarjan;
+use crate::literal::Variable;
+use crate::parse::{Clause,Formula};
+use crate::conflict::find_conflict;
+
+
+/// Checks whether a set of clauses can be met by building its implication graph
+/// and checking the SCCs of every variable used by the clauses
+pub fn clauses_satisfiable(clauses: &[Clause]) -> bool {
+
+    let mut graph = DirectedGraph::new();
+    let mut variables = BTreeSet::<Variable>::new();
+    {
+        let mut g = &mut graph;
+        for clause in clauses {
+            variables.insert(clause.first.var());
+            variables.insert(clause.second.var());
+            g.add_implication(clause.first.negate(), clause.second);
+            g.add_implication(clause.second.negate(), clause.first);
+        }
+    }
+
+    let mut k = Tarjan::new(&graph);
+    k.find_scc();
+    variables.iter().all(|v| k.get_group(v.positive().value()) != k.get_group(v.negative().value()))
+}
+
+/// Finds a minimal unsatisfiable subset of the formula's clauses (removing any one clause
+/// from the result makes it satisfiable).  Returns None if the formula can be satisfied.
+///
+/// The clauses along a conflicting implication cycle are already unsatisfiable, so the
+/// search starts from those and then drops each clause that isn't needed, so the graph must
+/// have been built with build_graph_with_provenance.
+pub fn find_unsat_core(graph: &DirectedGraph, k: &dyn SccFinder, formula: &Formula) -> Option<Vec<Clause>> {
+
+    let conflict = find_conflict(graph,k,formula)?;
+    let mut core : Vec<Clause> = conflict.clause_indexes().iter().map(|index| formula.clauses[*index].clone()).collect();
+    info!("Conflict for variable {} uses {} clauses",conflict.variable,core.len());
+
+    let mut index = 0;
+    while index < core.len() {
+        let removed = core.remove(index);
+        if clauses_satisfiable(&core) {
+            // clause is needed, so put it back
+            core.insert(index,removed);
+            index += 1;
+        }
+        else {
+            debug!("Clause {} on line {} not needed for the core",removed,removed.line);
+        }
+    }
+    info!("Unsatisfiable core has {} clauses",core.len());
+    Some(core)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::bruteforce::{brute_force,seeded_formula};
+    use crate::conflict::find_conflict;
+    use crate::dirgraph::DirectedGraph;
+    use crate::generator::GeneratorMode;
+    use crate::parse::{parse_formula,Clause,Formula,InputFormat,ParseMode};
+    use crate::scc::SccFinder;
+    use crate::tarjan::Tarjan;
+    use crate::unsatcore::{clauses_satisfiable,find_unsat_core};
+
+    fn core(formula: &mut Formula) -> Option<Vec<Clause>> {
+        let mut graph = DirectedGraph::new();
+        formula.build_graph_with_provenance(&mut graph);
+        let mut k = Tarjan::new(&graph);
+        k.find_scc();
+        find_unsat_core(&graph,&k,formula)
+    }
+
+    /// The core can't be met, but it can once any one clause is removed
+    fn check_minimal(core: &[Clause], context: &str) {
+        assert!(!clauses_satisfiable(core),"{} core {:?} can be met",context,core);
+        for index in 0..core.len() {
+            let mut smaller = core.to_vec();
+            smaller.remove(index);
+            assert!(clauses_satisfiable(&smaller),"{} core without {} can't be met",context,core[index]);
+        }
+    }
+
+    #[test]
+    fn minimal_core() {
+        // 1 and -1 are only in the same SCC because 2 and -2 are, so the conflict for 1 uses
+        // clauses that aren't needed
+        let mut formula = parse_formula("3\n-1 2\n1 -2\n2 2\n-2 -2\n-1 3\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        let found = core(&mut formula).unwrap();
+        check_minimal(&found,"two units");
+        let found : Vec<String> = found.iter().map(|clause| clause.to_string()).collect();
+        assert_eq!(found,vec!("(2 2)","(-2 -2)"));
+        let mut graph = DirectedGraph::new();
+        formula.build_graph(&mut graph);
+        let mut k = Tarjan::new(&graph);
+        k.find_scc();
+        assert_eq!(find_conflict(&graph,&k,&formula).unwrap().clause_indexes().len(),4);
+
+        let mut formula = parse_formula("2\n-1 2\n-2 1\n".as_bytes(),InputFormat::Course,ParseMode::Strict).unwrap();
+        assert_eq!(core(&mut formula),None);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut unsatisfiable = 0;
+        for seed in 0..200 {
+            let num_variables = 1 + (seed % 8) as usize;
+            let mut formula = seeded_formula(seed,GeneratorMode::Random,num_variables,num_variables*2);
+            let expected = brute_force(num_variables,&formula.clauses).is_some();
+            assert_eq!(clauses_satisfiable(&formula.clauses),expected,"seed {}",seed);
+            match core(&mut formula) {
+                Some(core) => {
+                    assert!(!expected,"seed {} found a core for a satisfiable formula",seed);
+                    unsatisfiable += 1;
+                    check_minimal(&core,&format!("seed {}",seed));
+                    assert!(brute_force(num_variables,&core).is_none(),"seed {}",seed);
+                    for index in 0..core.len() {
+                        let mut smaller = core.clone();
+                        smaller.remove(index);
+                        assert!(brute_force(num_variables,&smaller).is_some(),"seed {}",seed);
+                    }
+                },
+                None => assert!(expected,"seed {} found no core for an unsatisfiable formula",seed),
+            }
+        }
+        assert!(unsatisfiable > 20,"only {} unsatisfiable formulas",unsatisfiable);
+    }
+}